@@ -12,9 +12,9 @@ fn main() -> Result<(), String> {
     for parameter in &parameters {
         println!(
             "{} = {};\nдиапазон допустимых значений: {:?}\n",
-            config.get_parameter_description(&parameter)?,
-            config.get_parameter_value(&parameter)?,
-            config.get_parameter_possible_values(&parameter)?
+            config.get_parameter_description(parameter)?,
+            config.get_parameter_value(parameter)?,
+            config.get_parameter_possible_values(parameter)?
         );
     }
 
@@ -23,20 +23,20 @@ fn main() -> Result<(), String> {
     // Установка случайных значений для параметров в пределах допустимой области
     for parameter in &parameters {
         let random_value = config
-            .get_parameter_possible_values(&parameter)?
+            .get_parameter_possible_values(parameter)?
             .choose(&mut rng)
             .unwrap()
             .clone();
-        config.set_parameter_value(&parameter, random_value)?;
+        config.set_parameter_value(parameter, random_value)?;
     }
 
     println!("Значения параметров после изменений:\n");
     for parameter in &parameters {
         println!(
             "{} = {};\nдиапазон допустимых значений: {:?}\n",
-            config.get_parameter_description(&parameter)?,
-            config.get_parameter_value(&parameter)?,
-            config.get_parameter_possible_values(&parameter)?
+            config.get_parameter_description(parameter)?,
+            config.get_parameter_value(parameter)?,
+            config.get_parameter_possible_values(parameter)?
         );
     }
 
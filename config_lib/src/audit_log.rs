@@ -0,0 +1,294 @@
+//! # Журнал изменений параметров
+//!
+//! > Файл журнала только дополняется: одна запись в формате JSON на строку.
+//! > Запись добавляется при сохранении схемы для каждого параметра, значение которого изменилось.
+
+use log::warn;
+use serde_json::{Value, json};
+use std::io::Write;
+use std::path::PathBuf;
+use std::str::FromStr;
+use time::OffsetDateTime;
+use time::format_description::well_known::Rfc3339;
+
+/// Программа, выполнившая изменение
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditTool {
+    /// Меню настроек (menu_tui)
+    Menu,
+    /// Получение настроек от устройства (rk_nku_sync pull)
+    SyncPull,
+    /// Утилита командной строки (device-config)
+    Cli,
+}
+
+impl std::fmt::Display for AuditTool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AuditTool::Menu => write!(f, "menu"),
+            AuditTool::SyncPull => write!(f, "sync-pull"),
+            AuditTool::Cli => write!(f, "cli"),
+        }
+    }
+}
+
+impl FromStr for AuditTool {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "menu" => Ok(AuditTool::Menu),
+            "sync-pull" => Ok(AuditTool::SyncPull),
+            "cli" => Ok(AuditTool::Cli),
+            _ => Err(format!("Unknown audit tool: {s}")),
+        }
+    }
+}
+
+/// Запись журнала изменений
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuditEntry {
+    /// Время сохранения изменения (UTC)
+    pub timestamp: OffsetDateTime,
+    /// Путь к файлу-схеме
+    pub scheme: String,
+    /// Имя параметра
+    pub key: String,
+    /// Значение до изменения
+    pub old_value: String,
+    /// Значение после изменения
+    pub new_value: String,
+    /// Программа, выполнившая изменение
+    pub tool: AuditTool,
+}
+
+impl AuditEntry {
+    /// Представление записи в формате JSON
+    pub fn to_json(&self) -> Result<Value, String> {
+        Ok(json!({
+            "timestamp": format_timestamp(self.timestamp)?,
+            "scheme": self.scheme,
+            "key": self.key,
+            "old": self.old_value,
+            "new": self.new_value,
+            "tool": self.tool.to_string(),
+        }))
+    }
+
+    /// Чтение записи из строки журнала
+    fn from_line(line: &str) -> Result<Self, String> {
+        let record: Value = serde_json::from_str(line).map_err(|e| e.to_string())?;
+        let get_field = |field: &str| {
+            record[field]
+                .as_str()
+                .map(|value| value.to_string())
+                .ok_or(format!("Audit record has no {field} field: {line}"))
+        };
+
+        Ok(AuditEntry {
+            timestamp: parse_timestamp(&get_field("timestamp")?)?,
+            scheme: get_field("scheme")?,
+            key: get_field("key")?,
+            old_value: get_field("old")?,
+            new_value: get_field("new")?,
+            tool: get_field("tool")?.parse()?,
+        })
+    }
+}
+
+/// Условия выборки записей журнала, `None` - без ограничения
+#[derive(Debug, Clone, Default)]
+pub struct AuditQuery {
+    /// Путь к файлу-схеме
+    pub scheme: Option<String>,
+    /// Имя параметра
+    pub key: Option<String>,
+    /// Начало интервала времени (включительно)
+    pub since: Option<OffsetDateTime>,
+    /// Конец интервала времени (включительно)
+    pub until: Option<OffsetDateTime>,
+}
+
+impl AuditQuery {
+    fn matches(&self, entry: &AuditEntry) -> bool {
+        self.scheme
+            .as_ref()
+            .is_none_or(|scheme| scheme == &entry.scheme)
+            && self.key.as_ref().is_none_or(|key| key == &entry.key)
+            && self.since.is_none_or(|since| entry.timestamp >= since)
+            && self.until.is_none_or(|until| entry.timestamp <= until)
+    }
+}
+
+/// Журнал изменений в файле `path`
+#[derive(Debug, Clone)]
+pub struct AuditLog {
+    path: PathBuf,
+}
+
+impl AuditLog {
+    /// ## Открытие журнала `path`, файл создается при первой записи
+    pub fn open(path: &str) -> Self {
+        AuditLog {
+            path: PathBuf::from(path),
+        }
+    }
+
+    /// ## Добавление записей в конец журнала
+    pub fn append(&self, entries: &[AuditEntry]) -> Result<(), String> {
+        if entries.is_empty() {
+            return Ok(());
+        }
+
+        let mut lines = String::new();
+        for entry in entries {
+            lines.push_str(&entry.to_json()?.to_string());
+            lines.push('\n');
+        }
+
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|e| format!("Unable to open audit log {}: {e}", self.path.display()))?;
+        file.write_all(lines.as_bytes()).map_err(|e| e.to_string())
+    }
+
+    /// ## Выборка записей журнала по условиям `query` (в порядке записи)
+    /// Отсутствующий файл журнала - пустой журнал; поврежденные строки пропускаются с предупреждением
+    pub fn query(&self, query: &AuditQuery) -> Result<Vec<AuditEntry>, String> {
+        let content = match std::fs::read_to_string(&self.path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.to_string()),
+        };
+
+        let mut entries = Vec::new();
+        for (number, line) in content.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            match AuditEntry::from_line(line) {
+                Ok(entry) if query.matches(&entry) => entries.push(entry),
+                Ok(_) => {}
+                Err(e) => warn!(
+                    "Skipping audit log {} line {}: {e}",
+                    self.path.display(),
+                    number + 1
+                ),
+            }
+        }
+        Ok(entries)
+    }
+}
+
+/// ## Преобразование времени в строку формата RFC 3339 (например, `2025-03-01T12:00:00Z`)
+pub fn format_timestamp(timestamp: OffsetDateTime) -> Result<String, String> {
+    timestamp.format(&Rfc3339).map_err(|e| e.to_string())
+}
+
+/// ## Чтение времени из строки формата RFC 3339
+pub fn parse_timestamp(timestamp: &str) -> Result<OffsetDateTime, String> {
+    OffsetDateTime::parse(timestamp, &Rfc3339)
+        .map_err(|e| format!("Invalid timestamp {timestamp}: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCHEME: &str = "examples/simple_config.toml";
+
+    fn create_entry(key: &str, timestamp: &str, tool: AuditTool) -> AuditEntry {
+        AuditEntry {
+            timestamp: parse_timestamp(timestamp).unwrap(),
+            scheme: SCHEME.to_string(),
+            key: key.to_string(),
+            old_value: "1".to_string(),
+            new_value: "2".to_string(),
+            tool,
+        }
+    }
+
+    #[test]
+    fn test_append_and_query() {
+        let path =
+            std::env::temp_dir().join(format!("config_lib_audit_{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let audit_log = AuditLog::open(path.to_str().unwrap());
+
+        assert!(audit_log.query(&AuditQuery::default()).unwrap().is_empty());
+
+        audit_log
+            .append(&[
+                create_entry("loadcapacity", "2025-01-10T08:00:00Z", AuditTool::Menu),
+                create_entry("groupnumber", "2025-02-10T08:00:00Z", AuditTool::Cli),
+            ])
+            .unwrap();
+        audit_log
+            .append(&[create_entry(
+                "loadcapacity",
+                "2025-03-10T08:00:00Z",
+                AuditTool::SyncPull,
+            )])
+            .unwrap();
+
+        let all = audit_log.query(&AuditQuery::default()).unwrap();
+        assert_eq!(all.len(), 3);
+        assert_eq!(all[2].tool, AuditTool::SyncPull);
+
+        let by_key = audit_log
+            .query(&AuditQuery {
+                key: Some("loadcapacity".to_string()),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(by_key.len(), 2);
+
+        let by_time = audit_log
+            .query(&AuditQuery {
+                since: Some(parse_timestamp("2025-02-01T00:00:00Z").unwrap()),
+                until: Some(parse_timestamp("2025-02-28T00:00:00Z").unwrap()),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(
+            by_time,
+            vec![create_entry(
+                "groupnumber",
+                "2025-02-10T08:00:00Z",
+                AuditTool::Cli
+            )]
+        );
+
+        // Записи другой схемы и поврежденные строки не выводятся
+        let mut other_scheme = create_entry("groupnumber", "2025-04-10T08:00:00Z", AuditTool::Cli);
+        other_scheme.scheme = "examples/other_config.toml".to_string();
+        audit_log.append(&[other_scheme]).unwrap();
+        std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap()
+            .write_all(b"{\"timestamp\": \"2025-05-\n")
+            .unwrap();
+        audit_log
+            .append(&[create_entry(
+                "groupnumber",
+                "2025-06-10T08:00:00Z",
+                AuditTool::Menu,
+            )])
+            .unwrap();
+
+        let by_scheme = audit_log
+            .query(&AuditQuery {
+                scheme: Some(SCHEME.to_string()),
+                key: Some("groupnumber".to_string()),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(by_scheme.len(), 2);
+        assert_eq!(by_scheme[1].tool, AuditTool::Menu);
+        assert_eq!(audit_log.query(&AuditQuery::default()).unwrap().len(), 5);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
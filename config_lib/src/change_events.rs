@@ -0,0 +1,80 @@
+//! # События изменения значений параметров
+//!
+//! > Подписчики получают события через канал `std::sync::mpsc` (см. `DeviceConfig::subscribe`).
+
+use std::sync::mpsc::{Receiver, Sender, channel};
+
+use crate::device_config::ValueSource;
+
+/// Причина изменения значения параметра
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeSource {
+    /// Установка значения (`set_parameter_value`)
+    Set,
+    /// Сброс к значению по умолчанию
+    Reset,
+    /// Значение получено от интерфейсной платы
+    Device,
+    /// Загрузка значений из другого файла (например, архива устройств)
+    Import,
+    /// Повторное чтение файла-схемы
+    Reload,
+    /// Установка или сброс переопределения значения
+    Override(ValueSource),
+}
+
+impl std::fmt::Display for ChangeSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ChangeSource::Set => write!(f, "set"),
+            ChangeSource::Reset => write!(f, "reset"),
+            ChangeSource::Device => write!(f, "device"),
+            ChangeSource::Import => write!(f, "import"),
+            ChangeSource::Reload => write!(f, "reload"),
+            ChangeSource::Override(source) => write!(f, "override ({source})"),
+        }
+    }
+}
+
+/// Событие изменения действующего значения параметра
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChangeEvent {
+    /// Имя параметра
+    pub key: String,
+    /// Значение до изменения
+    pub old_value: String,
+    /// Значение после изменения
+    pub new_value: String,
+    /// Причина изменения
+    pub source: ChangeSource,
+}
+
+/// Список подписчиков на события изменения
+///
+/// При клонировании `DeviceConfig` подписчики не копируются: изменения копии
+/// не должны приходить подписчикам оригинала
+#[derive(Debug, Default)]
+pub(crate) struct ChangeObservers {
+    senders: Vec<Sender<ChangeEvent>>,
+}
+
+impl Clone for ChangeObservers {
+    fn clone(&self) -> Self {
+        ChangeObservers::default()
+    }
+}
+
+impl ChangeObservers {
+    /// Добавление подписчика
+    pub(crate) fn subscribe(&mut self) -> Receiver<ChangeEvent> {
+        let (sender, receiver) = channel();
+        self.senders.push(sender);
+        receiver
+    }
+
+    /// Отправка события всем подписчикам, отключившиеся подписчики удаляются
+    pub(crate) fn notify(&mut self, event: ChangeEvent) {
+        self.senders
+            .retain(|sender| sender.send(event.clone()).is_ok());
+    }
+}
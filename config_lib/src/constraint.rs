@@ -0,0 +1,80 @@
+/// Условие на значение одного параметра: параметр `key` принимает одно из `values`
+#[derive(Debug, Clone)]
+pub(crate) struct ValueCondition {
+    pub(crate) key: String,
+    pub(crate) values: Vec<String>,
+}
+
+impl ValueCondition {
+    /// Проверка выполнения условия для значения `value`
+    fn is_met(&self, value: &str) -> bool {
+        self.values.iter().any(|allowed| allowed == value)
+    }
+}
+
+/// Ограничение на сочетание значений нескольких параметров (таблица `[[constraints]]` в схеме)
+///
+/// Если выполнены все условия `when`, то параметры из `forbid` не должны принимать
+/// перечисленные значения, а параметры из `require` должны принимать одно из перечисленных
+#[derive(Debug, Clone)]
+pub(crate) struct Constraint {
+    /// Пояснение к ограничению (добавляется в текст ошибки)
+    pub(crate) message: Option<String>,
+    pub(crate) when: Vec<ValueCondition>,
+    pub(crate) forbid: Vec<ValueCondition>,
+    pub(crate) require: Vec<ValueCondition>,
+}
+
+impl Constraint {
+    /// Список всех параметров, упомянутых в ограничении
+    pub(crate) fn get_keys(&self) -> Vec<&str> {
+        self.when
+            .iter()
+            .chain(self.forbid.iter())
+            .chain(self.require.iter())
+            .map(|condition| condition.key.as_str())
+            .collect()
+    }
+
+    /// ## Проверка ограничения
+    /// * `get_value` - функция получения текущего значения параметра по ключу
+    pub(crate) fn check<F>(&self, get_value: F) -> Result<(), String>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let mut involved = Vec::new();
+
+        for condition in &self.when {
+            let value = get_value(&condition.key).unwrap_or_default();
+            if !condition.is_met(&value) {
+                return Ok(());
+            }
+            involved.push(format!("{} = {}", condition.key, value));
+        }
+
+        let mut conflicts = Vec::new();
+        for condition in &self.forbid {
+            let value = get_value(&condition.key).unwrap_or_default();
+            if condition.is_met(&value) {
+                conflicts.push(format!("{} = {}", condition.key, value));
+            }
+        }
+        for condition in &self.require {
+            let value = get_value(&condition.key).unwrap_or_default();
+            if !condition.is_met(&value) {
+                conflicts.push(format!("{} = {}", condition.key, value));
+            }
+        }
+
+        if conflicts.is_empty() {
+            return Ok(());
+        }
+
+        involved.extend(conflicts);
+        let mut error = format!("Conflicting parameters: {}", involved.join(", "));
+        if let Some(message) = &self.message {
+            error.push_str(&format!(" ({message})"));
+        }
+        Err(error)
+    }
+}
@@ -1,9 +1,146 @@
-use log::debug;
-use std::collections::HashMap;
+use log::{debug, warn};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::mpsc::Receiver;
+use std::sync::{Arc, Mutex};
 use toml_edit::value;
 
+use crate::audit_log::{AuditEntry, AuditLog, AuditTool};
+use crate::change_events::{ChangeEvent, ChangeObservers, ChangeSource};
+use crate::constraint::Constraint;
 use crate::toml_parser::*;
 
+/// Источник значения параметра
+///
+/// Порядок вариантов задает приоритет: значение из более позднего источника
+/// перекрывает значение из более раннего
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ValueSource {
+    /// Значение `current` из файла-схемы
+    Scheme,
+    /// Переменная окружения
+    Environment,
+    /// Аргумент командной строки вида `key=value`
+    CommandLine,
+}
+
+impl std::fmt::Display for ValueSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ValueSource::Scheme => write!(f, "scheme"),
+            ValueSource::Environment => write!(f, "environment"),
+            ValueSource::CommandLine => write!(f, "command line"),
+        }
+    }
+}
+
+/// Способ передачи значения параметра в интерфейсную плату (`encoding` в схеме)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WireEncoding {
+    /// Индекс значения в `possible_values`, не более `u8::MAX` (по умолчанию)
+    IndexU8,
+    /// Индекс значения в `possible_values`, не более `u16::MAX`
+    IndexU16,
+    /// Индекс значения в `possible_values`, не более `u32::MAX`
+    IndexU32,
+    /// Само значение из `possible_values` (например, обозначение этажа)
+    Label,
+}
+
+impl WireEncoding {
+    /// Максимальный индекс, который может быть передан в данной кодировке
+    fn get_max_index(&self) -> Option<usize> {
+        match self {
+            WireEncoding::IndexU8 => Some(u8::MAX as usize),
+            WireEncoding::IndexU16 => Some(u16::MAX as usize),
+            WireEncoding::IndexU32 => Some(u32::MAX as usize),
+            WireEncoding::Label => None,
+        }
+    }
+}
+
+impl std::fmt::Display for WireEncoding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WireEncoding::IndexU8 => write!(f, "u8"),
+            WireEncoding::IndexU16 => write!(f, "u16"),
+            WireEncoding::IndexU32 => write!(f, "u32"),
+            WireEncoding::Label => write!(f, "label"),
+        }
+    }
+}
+
+impl std::str::FromStr for WireEncoding {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "u8" => Ok(WireEncoding::IndexU8),
+            "u16" => Ok(WireEncoding::IndexU16),
+            "u32" => Ok(WireEncoding::IndexU32),
+            "label" => Ok(WireEncoding::Label),
+            _ => Err(format!("Unknown wire encoding: {s}")),
+        }
+    }
+}
+
+/// Ошибка сохранения значений параметров в файл-схему
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SaveError {
+    /// Сохраняемые значения нарушают ограничения схемы, файл-схема не изменена
+    Invalid(String),
+    /// Ошибка чтения/записи файла-схемы
+    Failed(String),
+}
+
+impl std::fmt::Display for SaveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SaveError::Invalid(message) | SaveError::Failed(message) => write!(f, "{message}"),
+        }
+    }
+}
+
+impl std::error::Error for SaveError {}
+
+impl From<SaveError> for String {
+    fn from(error: SaveError) -> Self {
+        error.to_string()
+    }
+}
+
+/// Описание параметра со всеми данными схемы и действующим значением
+/// Копия данных `DeviceConfig`: изменение полей не влияет на конфигурацию
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParameterInfo {
+    /// Имя параметра
+    pub key: String,
+    /// Описание параметра (для использования в меню)
+    pub description: String,
+    /// Действующее значение с учетом переопределения
+    pub value: String,
+    /// Источник действующего значения
+    pub source: ValueSource,
+    /// Значение, сохраняемое в схему
+    pub scheme_value: String,
+    /// Список допустимых значений параметра
+    pub possible_values: Vec<String>,
+    /// Значение по умолчанию
+    pub default: Option<String>,
+    /// Способ передачи значения в интерфейсную плату
+    pub encoding: WireEncoding,
+    /// Пояснение к параметру
+    pub help: Option<String>,
+    /// Единица измерения значения параметра
+    pub unit: Option<String>,
+    /// Предупреждение, показываемое при изменении параметра
+    pub warning: Option<String>,
+    /// Синонимы значений: допустимое значение и список его альтернативных написаний
+    pub value_aliases: Vec<(String, Vec<String>)>,
+    /// Прежние имена параметра
+    pub key_aliases: Vec<String>,
+    /// Сообщение об устаревании параметра
+    pub deprecated: Option<String>,
+}
+
 /// Структура, содержащая набор параметров` Parameter`
 #[derive(Debug, Clone)]
 pub struct DeviceConfig {
@@ -11,6 +148,19 @@ pub struct DeviceConfig {
     /// Набор параметров
     /// `key` - имя параметра, `value` - структура, описывающая параметр
     parameters: HashMap<String, Parameter>,
+    /// Ограничения на сочетания значений параметров
+    constraints: Vec<Constraint>,
+    /// Сопоставление значений без учета регистра, пробелов и единицы измерения
+    tolerant_matching: bool,
+    /// Устаревшие имена параметров: `key` - старое имя, `value` - актуальное имя
+    key_aliases: HashMap<String, String>,
+    /// Устаревшие имена параметров, об использовании которых уже выведено предупреждение
+    /// (общий для копий конфигурации, доступен из разных потоков)
+    warned_key_aliases: Arc<Mutex<HashSet<String>>>,
+    /// Подписчики на события изменения значений параметров
+    observers: ChangeObservers,
+    /// Журнал изменений и программа, от имени которой выполняется сохранение
+    audit: Option<(AuditLog, AuditTool)>,
 }
 
 impl DeviceConfig {
@@ -25,108 +175,647 @@ impl DeviceConfig {
 
         debug!("Parameter list: {:#?}", parameter_list);
         let mut parameter_map = HashMap::new();
+        let mut key_aliases = HashMap::new();
+
+        for parameter in parameter_list.iter() {
+            for alias in toml_config.get_optional_array_value(parameter, "aliases_for_key")? {
+                if parameter_list.contains(&alias)
+                    || key_aliases
+                        .insert(alias.clone(), parameter.clone())
+                        .is_some()
+                {
+                    return Err(format!("Parameter key alias {alias} is already in use"));
+                }
+            }
+
+            let encoding = match toml_config.get_optional_string_value(parameter, "encoding")? {
+                Some(encoding) => encoding.parse::<WireEncoding>()?,
+                None => WireEncoding::IndexU8,
+            };
+            let possible_values = toml_config.get_array_value(parameter, "possible_values")?;
+            if let Some(max_index) = encoding.get_max_index()
+                && possible_values.len() > max_index + 1
+            {
+                return Err(format!(
+                    "Parameter {parameter} has {} possible values, which overflows {encoding:?} encoding",
+                    possible_values.len()
+                ));
+            }
 
-        for parameter in parameter_list {
             let device_parameter = Parameter {
-                description: toml_config.get_string_value(&parameter, "name")?,
-                value: toml_config.get_string_value(&parameter, "current")?,
-                possible_values: toml_config.get_array_value(&parameter, "possible_values")?,
+                description: toml_config.get_string_value(parameter, "name")?,
+                value: toml_config.get_string_value(parameter, "current")?,
+                possible_values,
+                default: toml_config.get_optional_string_value(parameter, "default")?,
+                encoding,
+                help: toml_config.get_optional_string_value(parameter, "help")?,
+                unit: toml_config.get_optional_string_value(parameter, "unit")?,
+                warning: toml_config.get_optional_string_value(parameter, "warning")?,
+                aliases: toml_config.get_aliases(parameter)?,
+                deprecated: toml_config.get_optional_string_value(parameter, "deprecated")?,
+                override_value: None,
             };
-            parameter_map.insert(parameter, device_parameter);
+            parameter_map.insert(parameter.clone(), device_parameter);
         }
 
+        let constraints = toml_config.get_constraints(&key_aliases)?;
+
         let device_config = DeviceConfig {
             scheme: toml_config,
             parameters: parameter_map,
+            constraints,
+            tolerant_matching: false,
+            key_aliases,
+            warned_key_aliases: Arc::default(),
+            observers: ChangeObservers::default(),
+            audit: None,
         };
 
         Ok(device_config)
     }
 
+    /// ## Повторное чтение значений параметров из файла-схемы
+    /// Переопределения значений сохраняются; об изменившихся значениях уведомляются подписчики
+    pub fn reload_parameters_values(&mut self) -> Result<(), String> {
+        let mut reloaded = DeviceConfig::create_parameter_list(&self.get_path_to_scheme())?;
+
+        let mut events = Vec::new();
+        for (key, parameter) in reloaded.parameters.iter_mut() {
+            let Some(old_parameter) = self.parameters.get(key) else {
+                continue;
+            };
+            parameter.override_value = old_parameter.override_value.clone();
+
+            let (old_value, _) = old_parameter.get_effective_value();
+            let (new_value, _) = parameter.get_effective_value();
+            if old_value != new_value {
+                events.push(ChangeEvent {
+                    key: key.clone(),
+                    old_value,
+                    new_value,
+                    source: ChangeSource::Reload,
+                });
+            }
+        }
+
+        self.scheme = reloaded.scheme;
+        self.parameters = reloaded.parameters;
+        self.constraints = reloaded.constraints;
+        self.key_aliases = reloaded.key_aliases;
+
+        for event in events {
+            self.observers.notify(event);
+        }
+        Ok(())
+    }
+
+    /// Перенос подписчиков на события изменения из `other`
+    /// (при замене конфигурации ее измененной копией подписчики сохраняются)
+    pub(crate) fn take_observers_from(&mut self, other: &mut DeviceConfig) {
+        self.observers = std::mem::take(&mut other.observers);
+    }
+
+    /// ## Подписка на события изменения действующих значений параметров
+    /// События отправляются при установке, сбросе, импорте значений, переопределении и повторном чтении схемы
+    pub fn subscribe(&mut self) -> Receiver<ChangeEvent> {
+        self.observers.subscribe()
+    }
+
     /// ## Сохранение текущих значений параметров в TOML-файл `self.schema_path`
-    pub fn save_parameters_values(&self) -> Result<(), String> {
-        let mut toml_config =
-            TomlScheme::new(&self.scheme.get_path_to_scheme_file()).map_err(|e| e.to_string())?;
-        for (parameter_name, parameter_object) in &self.parameters {
-            toml_config.set_parameter_value(
-                parameter_name,
-                "current",
-                value(parameter_object.get_value()),
-            )?;
+    /// Сохранение не выполняется, если сохраняемые значения (без учета переопределений)
+    /// не проходят проверку `validate_scheme_values`
+    /// При подключенном журнале изменений (`set_audit_log`) в него записываются измененные значения
+    pub fn save_parameters_values(&self) -> Result<(), SaveError> {
+        self.validate_scheme_values().map_err(SaveError::Invalid)?;
+        self.write_parameters_values().map_err(SaveError::Failed)
+    }
+
+    /// Запись значений параметров в журнал изменений и файл-схему
+    /// Журнал дополняется до записи схемы: ошибка журнала отменяет сохранение
+    fn write_parameters_values(&self) -> Result<(), String> {
+        let scheme_path = self.scheme.get_path_to_scheme_file();
+        let mut toml_config = TomlScheme::new(&scheme_path).map_err(|e| e.to_string())?;
+        let now = time::OffsetDateTime::now_utc();
+        let timestamp = now.replace_nanosecond(0).unwrap_or(now);
+        let mut audit_entries = Vec::new();
+        let mut values = Vec::new();
+
+        for parameter_name in self.get_parameters_names()? {
+            let new_value = self.get_parameter(&parameter_name)?.get_scheme_value();
+            let old_value = toml_config.get_string_value(&parameter_name, "current")?;
+
+            if let Some((_, tool)) = &self.audit
+                && old_value != new_value
+            {
+                audit_entries.push(AuditEntry {
+                    timestamp,
+                    scheme: scheme_path.clone(),
+                    key: parameter_name.clone(),
+                    old_value,
+                    new_value: new_value.clone(),
+                    tool: *tool,
+                });
+            }
+            values.push((parameter_name, new_value));
+        }
+
+        if let Some((audit_log, _)) = &self.audit {
+            audit_log.append(&audit_entries)?;
+        }
+        for (parameter_name, new_value) in values {
+            toml_config.set_parameter_value(&parameter_name, "current", value(new_value))?;
         }
         Ok(())
     }
 
-    /// ## Получение списка ключей - имен параметров
+    /// ## Подключение журнала изменений `audit_log`
+    /// * `tool` - программа, от имени которой сохраняются значения
+    pub fn set_audit_log(&mut self, audit_log: AuditLog, tool: AuditTool) {
+        self.audit = Some((audit_log, tool));
+    }
+
+    /// ## Проверка действующих значений параметров
+    /// Каждое значение должно входить в список допустимых значений, сочетания значений -
+    /// удовлетворять ограничениям схемы. Возвращает описание всех нарушений
+    pub fn validate(&self) -> Result<(), String> {
+        self.check_values(|parameter| parameter.get_effective_value().0)
+    }
+
+    /// ## Проверка значений, сохраняемых в схему (см. `validate`)
+    /// Переопределения значений не учитываются
+    pub fn validate_scheme_values(&self) -> Result<(), String> {
+        self.check_values(Parameter::get_scheme_value)
+    }
+
+    /// Проверка значений параметров, возвращаемых `get_value`
+    fn check_values<F>(&self, get_value: F) -> Result<(), String>
+    where
+        F: Fn(&Parameter) -> String,
+    {
+        let mut violations = Vec::new();
+        for key in self.get_parameters_names()? {
+            let parameter = self.get_parameter(&key)?;
+            let value = get_value(parameter);
+            if !parameter.possible_values.contains(&value) {
+                violations.push(format!("Parameter {key} has invalid value {value}"));
+            }
+        }
+
+        violations.extend(self.constraints.iter().filter_map(|constraint| {
+            constraint
+                .check(|key| self.parameters.get(key).map(&get_value))
+                .err()
+        }));
+
+        if violations.is_empty() {
+            return Ok(());
+        }
+        Err(violations.join("\n"))
+    }
+
+    /// ## Получение списка ключей - имен параметров (в порядке следования в схеме)
     pub fn get_parameters_names(&self) -> Result<Vec<String>, String> {
         if self.parameters.is_empty() {
             return Err("Parameters list is empty".to_string());
         }
 
-        return Ok(self.parameters.keys().map(|key| key.clone()).collect());
+        self.scheme.get_list_of_parameters()
+    }
+
+    /// ## Получение пути к файлу-схеме
+    pub fn get_path_to_scheme(&self) -> String {
+        self.scheme.get_path_to_scheme_file()
+    }
+
+    /// ## Получение описаний всех параметров в порядке следования в схеме
+    pub fn parameters(&self) -> impl Iterator<Item = ParameterInfo> + '_ {
+        self.scheme
+            .get_list_of_parameters()
+            .into_iter()
+            .flatten()
+            .filter_map(|key| self.get_parameter_info(&key).ok())
     }
 
     /// ## Получение описания параметра соответствующего `key`
-    pub fn get_parameter_description(&self, key: &str) -> Result<String, String> {
-        match self.parameters.get(key) {
-            Some(parameter) => Ok(parameter.get_description()),
-            None => Err(format!("Parameter {} not found", key)),
+    pub fn get_parameter_info(&self, key: &str) -> Result<ParameterInfo, String> {
+        let key = self.resolve_parameter_key(key)?;
+        let parameter = self.get_parameter(&key)?;
+        let (value, source) = parameter.get_effective_value();
+
+        Ok(ParameterInfo {
+            key_aliases: self.get_parameter_key_aliases(&key)?,
+            key,
+            description: parameter.get_description(),
+            value,
+            source,
+            scheme_value: parameter.get_scheme_value(),
+            possible_values: parameter.get_possible_values(),
+            default: parameter.default.clone(),
+            encoding: parameter.encoding,
+            help: parameter.help.clone(),
+            unit: parameter.unit.clone(),
+            warning: parameter.warning.clone(),
+            value_aliases: parameter.aliases.clone(),
+            deprecated: parameter.deprecated.clone(),
+        })
+    }
+
+    /// ## Получение действующих значений всех параметров (`key` - имя параметра)
+    pub fn get_parameters_values(&self) -> Result<BTreeMap<String, String>, String> {
+        let mut values = BTreeMap::new();
+        for key in self.get_parameters_names()? {
+            let parameter_value = self.get_parameter_value(&key)?;
+            values.insert(key, parameter_value);
+        }
+        Ok(values)
+    }
+
+    /// ## Получение значений всех параметров, сохраняемых в схему (без учета переопределений)
+    pub fn get_parameters_scheme_values(&self) -> Result<BTreeMap<String, String>, String> {
+        let mut values = BTreeMap::new();
+        for key in self.get_parameters_names()? {
+            let parameter_value = self.get_parameter(&key)?.get_scheme_value();
+            values.insert(key, parameter_value);
         }
+        Ok(values)
+    }
+
+    /// ## Получение описания параметра соответствующего `key`
+    pub fn get_parameter_description(&self, key: &str) -> Result<String, String> {
+        self.get_parameter(key)
+            .map(|parameter| parameter.get_description())
     }
 
     /// ## Получение списка возможных значений параметра соответствующего `key`
     pub fn get_parameter_possible_values(&self, key: &str) -> Result<Vec<String>, String> {
-        match self.parameters.get(key) {
-            Some(parameter) => Ok(parameter.get_possible_values()),
-            None => Err(format!("Parameter {} not found", key)),
-        }
+        self.get_parameter(key)
+            .map(|parameter| parameter.get_possible_values())
+    }
+
+    /// ## Получение пояснения к параметру соответствующему `key` (`help` в схеме)
+    pub fn get_parameter_help(&self, key: &str) -> Result<Option<String>, String> {
+        self.get_parameter(key)
+            .map(|parameter| parameter.help.clone())
+    }
+
+    /// ## Получение единицы измерения параметра соответствующего `key` (`unit` в схеме)
+    pub fn get_parameter_unit(&self, key: &str) -> Result<Option<String>, String> {
+        self.get_parameter(key)
+            .map(|parameter| parameter.unit.clone())
+    }
+
+    /// ## Получение предупреждения для параметра соответствующего `key` (`warning` в схеме)
+    pub fn get_parameter_warning(&self, key: &str) -> Result<Option<String>, String> {
+        self.get_parameter(key)
+            .map(|parameter| parameter.warning.clone())
+    }
+
+    /// ## Получение значения по умолчанию параметра соответствующего `key` (`default` в схеме)
+    pub fn get_parameter_default(&self, key: &str) -> Result<Option<String>, String> {
+        self.get_parameter(key)
+            .map(|parameter| parameter.default.clone())
+    }
+
+    /// ## Получение синонимов значений параметра соответствующего `key` (`aliases` в схеме)
+    pub fn get_parameter_value_aliases(
+        &self,
+        key: &str,
+    ) -> Result<Vec<(String, Vec<String>)>, String> {
+        self.get_parameter(key)
+            .map(|parameter| parameter.aliases.clone())
+    }
+
+    /// ## Получение прежних имен параметра соответствующего `key` (`aliases_for_key` в схеме)
+    pub fn get_parameter_key_aliases(&self, key: &str) -> Result<Vec<String>, String> {
+        let key = self.resolve_parameter_key(key)?;
+        let mut aliases: Vec<String> = self
+            .key_aliases
+            .iter()
+            .filter(|(_, actual_key)| **actual_key == key)
+            .map(|(alias, _)| alias.clone())
+            .collect();
+        aliases.sort();
+        Ok(aliases)
     }
 
     /// ## Получение значения параметра соответствующего `key`
+    /// Если для параметра задано переопределение, возвращается переопределенное значение
     pub fn get_parameter_value(&self, key: &str) -> Result<String, String> {
-        match self.parameters.get(key) {
-            Some(parameter) => Ok(parameter.get_value()),
+        self.get_parameter_value_with_source(key)
+            .map(|(value, _)| value)
+    }
+
+    /// ## Получение значения параметра соответствующего `key` вместе с его источником
+    pub fn get_parameter_value_with_source(
+        &self,
+        key: &str,
+    ) -> Result<(String, ValueSource), String> {
+        self.get_parameter(key)
+            .map(|parameter| parameter.get_effective_value())
+    }
+
+    /// ## Проверка наличия параметра `key` в схеме
+    /// Учитываются устаревшие имена параметров (`aliases_for_key` в схеме)
+    pub fn has_parameter(&self, key: &str) -> bool {
+        self.parameters.contains_key(key) || self.key_aliases.contains_key(key)
+    }
+
+    /// ## Получение актуального имени параметра по ключу `key`
+    /// Для устаревшего имени (`aliases_for_key` в схеме) возвращается новое имя,
+    /// предупреждение об устаревшем имени выводится один раз
+    pub fn resolve_parameter_key(&self, key: &str) -> Result<String, String> {
+        if self.parameters.contains_key(key) {
+            return Ok(key.to_string());
+        }
+        match self.key_aliases.get(key) {
+            Some(actual_key) => {
+                let mut warned_key_aliases = match self.warned_key_aliases.lock() {
+                    Ok(warned_key_aliases) => warned_key_aliases,
+                    Err(poisoned) => poisoned.into_inner(),
+                };
+                if warned_key_aliases.insert(key.to_string()) {
+                    warn!("Parameter key {key} is deprecated, use {actual_key} instead");
+                }
+                Ok(actual_key.clone())
+            }
             None => Err(format!("Parameter {} not found", key)),
         }
     }
 
+    /// ## Получение сообщения об устаревании параметра `key` (`deprecated` в схеме)
+    pub fn get_parameter_deprecation(&self, key: &str) -> Result<Option<String>, String> {
+        self.get_parameter(key)
+            .map(|parameter| parameter.deprecated.clone())
+    }
+
+    /// ## Включение/отключение нестрогого сопоставления значений параметров
+    /// При включенном режиме регистр, пробелы и единица измерения (`unit`) не учитываются,
+    /// например `"100"` соответствует `"100%"`. Значение приводится к варианту из `possible_values`
+    pub fn set_tolerant_matching(&mut self, enabled: bool) {
+        self.tolerant_matching = enabled;
+    }
+
+    /// ## Приведение значения `value` параметра `key` к варианту из `possible_values`
+    /// Учитываются синонимы значений (`aliases` в схеме) и режим нестрогого сопоставления
+    pub fn resolve_parameter_value(&self, key: &str, value: &str) -> Result<String, String> {
+        let parameter = self.get_parameter(key)?;
+        parameter
+            .resolve_value(value, self.tolerant_matching)
+            .ok_or(format!(
+                "Parameter {} cannot be set to {}!",
+                parameter.description, value
+            ))
+    }
+
+    /// ## Переопределение значения параметра `key` без изменения файла-схемы
+    /// Переопределенное значение не сохраняется в `save_parameters_values`.
+    /// Переопределение из источника с более низким приоритетом игнорируется
+    pub fn set_parameter_override(
+        &mut self,
+        key: &str,
+        value: String,
+        source: ValueSource,
+    ) -> Result<(), String> {
+        self.track_change(
+            key,
+            ChangeSource::Override(source),
+            |parameter, tolerant| {
+                parameter.set_override(value, source, tolerant);
+                Ok(())
+            },
+        )
+    }
+
+    /// ## Сброс переопределения значения параметра `key`
+    pub fn clear_parameter_override(&mut self, key: &str) -> Result<(), String> {
+        self.track_change(
+            key,
+            ChangeSource::Override(ValueSource::Scheme),
+            |parameter, _| {
+                parameter.override_value = None;
+                Ok(())
+            },
+        )
+    }
+
+    /// ## Переопределение значений параметров из переменных окружения
+    /// Имя переменной: `prefix` + имя параметра в верхнем регистре (например, `NKU_BAUDRATE`).
+    /// Переменные с устаревшими именами параметров также учитываются
+    pub fn apply_env_overrides(&mut self, prefix: &str) {
+        self.apply_env_overrides_with(prefix, |variable_name| std::env::var(variable_name).ok());
+    }
+
+    /// ## Переопределение значений параметров из переменных, значения которых возвращает `lookup`
+    /// Имена переменных формируются так же, как в `apply_env_overrides`
+    pub fn apply_env_overrides_with<F>(&mut self, prefix: &str, lookup: F)
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let keys = self
+            .key_aliases
+            .iter()
+            .map(|(alias, actual_key)| (alias.as_str(), actual_key.as_str()))
+            .chain(
+                self.parameters
+                    .keys()
+                    .map(|key| (key.as_str(), key.as_str())),
+            )
+            .map(|(key, actual_key)| (key.to_string(), actual_key.to_string()))
+            .collect::<Vec<(String, String)>>();
+
+        for (key, actual_key) in keys {
+            let variable_name = format!("{}{}", prefix, key.to_uppercase());
+            if let Some(value) = lookup(&variable_name) {
+                if key != actual_key {
+                    warn!(
+                        "{variable_name} is deprecated, use {prefix}{}",
+                        actual_key.to_uppercase()
+                    );
+                }
+                debug!("{actual_key} overridden by {variable_name}={value}");
+                let _ = self.set_parameter_override(&actual_key, value, ValueSource::Environment);
+            }
+        }
+    }
+
+    /// ## Переопределение значений параметров из аргументов вида `key=value`
+    pub fn apply_cli_overrides(&mut self, arguments: &[String]) -> Result<(), String> {
+        for argument in arguments {
+            let (key, value) = parse_override(argument)?;
+            self.set_parameter_override(&key, value, ValueSource::CommandLine)?;
+        }
+        Ok(())
+    }
+
     /// ## Установка значения параметра соответствующего `key`
+    /// Значение сохраняется в схему; активное переопределение при этом не сбрасывается
     pub fn set_parameter_value(&mut self, key: &str, value: String) -> Result<(), String> {
-        match self.parameters.get_mut(key) {
-            Some(parameter) => parameter.set_value(value),
-            None => Err(format!("Parameter {} not found", key)),
+        self.set_parameter_value_from(key, value, ChangeSource::Set)
+    }
+
+    /// ## Установка значения параметра соответствующего `key` с указанием причины изменения `source`
+    /// Причина передается подписчикам в `ChangeEvent`
+    pub fn set_parameter_value_from(
+        &mut self,
+        key: &str,
+        value: String,
+        source: ChangeSource,
+    ) -> Result<(), String> {
+        if let Some(deprecation) = &self.get_parameter(key)?.deprecated {
+            warn!("Parameter {key} is deprecated: {deprecation}");
         }
+        self.track_change(key, source, |parameter, tolerant| {
+            parameter.set_value(value, tolerant)
+        })
+    }
+
+    /// ## Сброс параметра `key` к значению по умолчанию (`default` в схеме)
+    pub fn reset_parameter_value(&mut self, key: &str) -> Result<(), String> {
+        let Some(default) = self.get_parameter_default(key)? else {
+            return Err(format!("Parameter {key} has no default value"));
+        };
+        self.set_parameter_value_from(key, default, ChangeSource::Reset)
     }
 
     /// ## Получение числового индекса соответствующего текущему значению параметра
     /// Индекс соответствует положению текущего значения в списке возможных значений `possible_values`
-    pub fn get_parameter_index_using_value(&self, key: &str) -> Result<u8, String> {
-        let parameters_list = self.get_parameters_names()?;
-        if parameters_list.contains(&key.to_owned()) {
-            let parameters_possible_values_list = self.get_parameter_possible_values(key)?;
-            let parameter_current_value = self.get_parameter_value(key)?;
+    pub fn get_parameter_index_using_value(&self, key: &str) -> Result<usize, String> {
+        let parameters_possible_values_list = self.get_parameter_possible_values(key)?;
+        let parameter_current_value = self.get_parameter_value(key)?;
 
-            if let Some(index) = parameters_possible_values_list
-                .iter()
-                .position(|value| *value == parameter_current_value)
-            {
-                return Ok(index as u8);
-            }
-        }
-        Err("Unable to get parameter value using index".to_string())
+        parameters_possible_values_list
+            .iter()
+            .position(|value| *value == parameter_current_value)
+            .ok_or(format!(
+                "Value {parameter_current_value} of {key} is not one of possible values"
+            ))
     }
 
     /// ## Присваивание параметру `key` значения, соответствующего `index`
     /// Индекс соответствует  положению  значения в списке возможных значений `possible_values`
-    pub fn set_parameter_value_using_index(&mut self, key: &str, index: u8) -> Result<(), String> {
-        let parameters_list = self.get_parameters_names()?;
-        if parameters_list.contains(&key.to_owned()) {
-            let parameters_possible_values_list = self.get_parameter_possible_values(key)?;
-            if let Some(value) = parameters_possible_values_list.get(index as usize) {
-                return self.set_parameter_value(key, value.clone());
-            }
+    pub fn set_parameter_value_using_index(
+        &mut self,
+        key: &str,
+        index: usize,
+    ) -> Result<(), String> {
+        let parameters_possible_values_list = self.get_parameter_possible_values(key)?;
+        match parameters_possible_values_list.get(index) {
+            Some(value) => self.set_parameter_value(key, value.clone()),
+            None => Err(format!(
+                "Index {index} of {key} is out of range ({} possible values)",
+                parameters_possible_values_list.len()
+            )),
+        }
+    }
+
+    /// ## Получение способа передачи значения параметра `key` в интерфейсную плату
+    pub fn get_parameter_encoding(&self, key: &str) -> Result<WireEncoding, String> {
+        self.get_parameter(key).map(|parameter| parameter.encoding)
+    }
+
+    /// ## Получение текущего значения параметра `key` в виде для передачи в интерфейсную плату
+    /// Для индексных кодировок проверяется, что индекс помещается в разрядность кодировки
+    pub fn get_parameter_wire_value(&self, key: &str) -> Result<String, String> {
+        let encoding = self.get_parameter_encoding(key)?;
+        if encoding == WireEncoding::Label {
+            return self.get_parameter_value(key);
+        }
+
+        let index = self.get_parameter_index_using_value(key)?;
+        match encoding.get_max_index() {
+            Some(max_index) if index > max_index => Err(format!(
+                "Index {index} of {key} overflows {encoding:?} encoding (max {max_index})"
+            )),
+            _ => Ok(index.to_string()),
+        }
+    }
+
+    /// ## Установка значения параметра `key` из значения, полученного от интерфейсной платы
+    pub fn set_parameter_value_from_wire(
+        &mut self,
+        key: &str,
+        wire_value: &str,
+    ) -> Result<(), String> {
+        let encoding = self.get_parameter_encoding(key)?;
+        if encoding == WireEncoding::Label {
+            return self.set_parameter_value_from(
+                key,
+                wire_value.to_string(),
+                ChangeSource::Device,
+            );
+        }
+
+        let index = wire_value
+            .trim()
+            .parse::<usize>()
+            .map_err(|e| format!("Unable to parse index {wire_value} of {key}: {e}"))?;
+        if let Some(max_index) = encoding.get_max_index()
+            && index > max_index
+        {
+            return Err(format!(
+                "Index {index} of {key} overflows {encoding:?} encoding (max {max_index})"
+            ));
+        }
+
+        let possible_values = self.get_parameter_possible_values(key)?;
+        match possible_values.get(index) {
+            Some(value) => self.set_parameter_value_from(key, value.clone(), ChangeSource::Device),
+            None => Err(format!(
+                "Index {index} of {key} is out of range ({} possible values)",
+                possible_values.len()
+            )),
         }
-        Err("Unable to get parameter value using index".to_string())
+    }
+
+    /// Выполнение изменения `change` параметра `key` с уведомлением подписчиков,
+    /// если действующее значение параметра изменилось
+    fn track_change<F>(&mut self, key: &str, source: ChangeSource, change: F) -> Result<(), String>
+    where
+        F: FnOnce(&mut Parameter, bool) -> Result<(), String>,
+    {
+        let key = self.resolve_parameter_key(key)?;
+        let tolerant = self.tolerant_matching;
+        let parameter = self.get_parameter_mut(&key)?;
+
+        let (old_value, _) = parameter.get_effective_value();
+        change(parameter, tolerant)?;
+        let (new_value, _) = parameter.get_effective_value();
+
+        if old_value != new_value {
+            self.observers.notify(ChangeEvent {
+                key,
+                old_value,
+                new_value,
+                source,
+            });
+        }
+        Ok(())
+    }
+
+    /// Получение параметра по ключу `key` (в том числе по устаревшему имени)
+    fn get_parameter(&self, key: &str) -> Result<&Parameter, String> {
+        let key = self.resolve_parameter_key(key)?;
+        self.parameters
+            .get(&key)
+            .ok_or(format!("Parameter {} not found", key))
+    }
+
+    /// Получение изменяемого параметра по ключу `key` (в том числе по устаревшему имени)
+    fn get_parameter_mut(&mut self, key: &str) -> Result<&mut Parameter, String> {
+        let key = self.resolve_parameter_key(key)?;
+        self.parameters
+            .get_mut(&key)
+            .ok_or(format!("Parameter {} not found", key))
+    }
+}
+
+/// ## Разбор аргумента переопределения вида `key=value`
+pub fn parse_override(argument: &str) -> Result<(String, String), String> {
+    match argument.split_once('=') {
+        Some((key, value)) if !key.trim().is_empty() => {
+            Ok((key.trim().to_string(), value.trim().to_string()))
+        }
+        _ => Err(format!("Invalid override {argument}, expected key=value")),
     }
 }
 
@@ -139,6 +828,22 @@ struct Parameter {
     value: String,
     /// Список допустимых значений параметра
     possible_values: Vec<String>,
+    /// Значение по умолчанию
+    default: Option<String>,
+    /// Способ передачи значения в интерфейсную плату
+    encoding: WireEncoding,
+    /// Пояснение к параметру (необязательно)
+    help: Option<String>,
+    /// Единица измерения значения параметра (необязательно)
+    unit: Option<String>,
+    /// Предупреждение, показываемое при изменении параметра (необязательно)
+    warning: Option<String>,
+    /// Синонимы значений: допустимое значение и список его альтернативных написаний
+    aliases: Vec<(String, Vec<String>)>,
+    /// Сообщение об устаревании параметра (необязательно)
+    deprecated: Option<String>,
+    /// Переопределенное значение и его источник (не сохраняется в схему)
+    override_value: Option<(String, ValueSource)>,
 }
 
 impl Parameter {
@@ -147,21 +852,115 @@ impl Parameter {
         self.description.clone()
     }
 
-    /// Получение текущего значения параметра
-    fn get_value(&self) -> String {
+    /// Получение текущего значения параметра из схемы
+    fn get_scheme_value(&self) -> String {
         self.value.clone()
     }
 
+    /// Получение действующего значения параметра с учетом переопределения
+    fn get_effective_value(&self) -> (String, ValueSource) {
+        match &self.override_value {
+            Some((value, source)) => (value.clone(), *source),
+            None => (self.value.clone(), ValueSource::Scheme),
+        }
+    }
+
+    /// Установка переопределенного значения параметра
+    fn set_override(&mut self, value: String, source: ValueSource, tolerant: bool) {
+        if let Some((_, current_source)) = &self.override_value
+            && *current_source > source
+        {
+            return;
+        }
+        let value = match self.resolve_value(&value, tolerant) {
+            Some(resolved) => resolved,
+            None => {
+                warn!(
+                    "Parameter {} is overridden by {} with value {} outside of possible values",
+                    self.description, source, value
+                );
+                value
+            }
+        };
+        self.override_value = Some((value, source));
+    }
+
     /// Установка текущего значения параметра
-    fn set_value(&mut self, value: String) -> Result<(), String> {
-        if !self.possible_values.contains(&value) {
-            return Err(format!(
+    fn set_value(&mut self, value: String, tolerant: bool) -> Result<(), String> {
+        match self.resolve_value(&value, tolerant) {
+            Some(resolved) => {
+                self.value = resolved;
+                Ok(())
+            }
+            None => Err(format!(
                 "Parameter {} cannot be set to {}!",
                 self.description, value
-            ));
+            )),
         }
-        self.value = value;
-        Ok(())
+    }
+
+    /// Поиск допустимого значения, соответствующего `value`
+    /// Порядок поиска: точное совпадение, синоним, нестрогое совпадение (если `tolerant`)
+    fn resolve_value(&self, value: &str, tolerant: bool) -> Option<String> {
+        if self
+            .possible_values
+            .iter()
+            .any(|possible| possible == value)
+        {
+            return Some(value.to_string());
+        }
+
+        if let Some((canonical, _)) = self
+            .aliases
+            .iter()
+            .find(|(_, aliases)| aliases.iter().any(|alias| alias == value))
+        {
+            return Some(canonical.clone());
+        }
+
+        if !tolerant {
+            return None;
+        }
+
+        let normalized = self.normalize_value(value);
+        if let Some(possible) = self
+            .possible_values
+            .iter()
+            .find(|possible| self.normalize_value(possible) == normalized)
+        {
+            return Some(possible.clone());
+        }
+
+        self.aliases
+            .iter()
+            .find(|(_, aliases)| {
+                aliases
+                    .iter()
+                    .any(|alias| self.normalize_value(alias) == normalized)
+            })
+            .map(|(canonical, _)| canonical.clone())
+    }
+
+    /// Приведение значения к виду для нестрогого сравнения:
+    /// без пробелов, в нижнем регистре, без единицы измерения в конце
+    fn normalize_value(&self, value: &str) -> String {
+        let mut normalized: String = value
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .collect::<String>()
+            .to_lowercase();
+
+        if let Some(unit) = &self.unit {
+            let unit: String = unit
+                .chars()
+                .filter(|c| !c.is_whitespace())
+                .collect::<String>()
+                .to_lowercase();
+            if !unit.is_empty() && normalized.ends_with(&unit) {
+                normalized.truncate(normalized.len() - unit.len());
+            }
+        }
+        normalized
     }
 
     /// Получение списка возможных значений параметра
@@ -203,8 +1002,8 @@ mod tests {
 
     #[test]
     fn test_parameter_saving() {
-        let mut device_config =
-            DeviceConfig::create_parameter_list("examples/simple_config.toml").unwrap();
+        let scheme_path = crate::copy_example_scheme("saving");
+        let mut device_config = DeviceConfig::create_parameter_list(&scheme_path).unwrap();
 
         let names = device_config.get_parameters_names().unwrap();
 
@@ -224,13 +1023,67 @@ mod tests {
 
         assert!(device_config.save_parameters_values().is_ok());
 
-        let device_config =
-            DeviceConfig::create_parameter_list("examples/simple_config.toml").unwrap();
+        let device_config = DeviceConfig::create_parameter_list(&scheme_path).unwrap();
 
         assert_eq!(
             device_config.get_parameter_value("soundvolume").unwrap(),
             "100%"
         );
+
+        std::fs::remove_file(&scheme_path).unwrap();
+    }
+
+    #[test]
+    fn test_audit_log_on_save() {
+        use crate::audit_log::AuditQuery;
+
+        let scheme_path = crate::copy_example_scheme("audit");
+        let audit_path = std::env::temp_dir().join(format!(
+            "config_lib_audit_save_{}.jsonl",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&audit_path);
+
+        let mut device_config = DeviceConfig::create_parameter_list(&scheme_path).unwrap();
+        device_config.set_audit_log(AuditLog::open(audit_path.to_str().unwrap()), AuditTool::Cli);
+
+        device_config
+            .set_parameter_value("groupnumber", "3".to_string())
+            .unwrap();
+        device_config
+            .set_parameter_value("soundvolume", "25%".to_string())
+            .unwrap();
+        device_config.save_parameters_values().unwrap();
+        // Повторное сохранение без изменений не добавляет записей
+        device_config.save_parameters_values().unwrap();
+
+        let entries = AuditLog::open(audit_path.to_str().unwrap())
+            .query(&AuditQuery {
+                key: Some("soundvolume".to_string()),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].new_value, "25%");
+        assert_eq!(entries[0].tool, AuditTool::Cli);
+
+        // Изменение не сохраняется, если его не удалось записать в журнал
+        let saved = std::fs::read_to_string(&scheme_path).unwrap();
+        device_config.set_audit_log(
+            AuditLog::open(std::env::temp_dir().to_str().unwrap()),
+            AuditTool::Cli,
+        );
+        device_config
+            .set_parameter_value("soundvolume", "75%".to_string())
+            .unwrap();
+        assert!(matches!(
+            device_config.save_parameters_values(),
+            Err(SaveError::Failed(_))
+        ));
+        assert_eq!(std::fs::read_to_string(&scheme_path).unwrap(), saved);
+
+        std::fs::remove_file(&scheme_path).unwrap();
+        std::fs::remove_file(&audit_path).unwrap();
     }
 
     #[test]
@@ -250,7 +1103,7 @@ mod tests {
                 device_config
                     .get_parameter_index_using_value("groupnumber")
                     .unwrap(),
-                index as u8
+                index
             );
         }
 
@@ -258,15 +1111,15 @@ mod tests {
             .get_parameter_possible_values("loadcapacity")
             .unwrap();
 
-        for index in 0..capacity_values.len() {
+        for (index, capacity_value) in capacity_values.iter().enumerate() {
             device_config
-                .set_parameter_value("loadcapacity", capacity_values[index].clone())
+                .set_parameter_value("loadcapacity", capacity_value.clone())
                 .unwrap();
             assert_eq!(
                 device_config
                     .get_parameter_index_using_value("loadcapacity")
                     .unwrap(),
-                index as u8
+                index
             );
         }
     }
@@ -282,7 +1135,7 @@ mod tests {
             .len()
         {
             device_config
-                .set_parameter_value_using_index("groupnumber", index as u8)
+                .set_parameter_value_using_index("groupnumber", index)
                 .unwrap();
             assert_eq!(
                 device_config.get_parameter_value("groupnumber").unwrap(),
@@ -296,7 +1149,7 @@ mod tests {
             .len()
         {
             device_config
-                .set_parameter_value_using_index("loadcapacity", index as u8)
+                .set_parameter_value_using_index("loadcapacity", index)
                 .unwrap();
             assert_eq!(
                 device_config.get_parameter_value("loadcapacity").unwrap(),
@@ -306,4 +1159,395 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_parameter_overrides() {
+        let scheme_path = crate::copy_example_scheme("overrides");
+        let mut device_config = DeviceConfig::create_parameter_list(&scheme_path).unwrap();
+
+        let scheme_value = device_config.get_parameter_value("musicvolume").unwrap();
+
+        let environment =
+            HashMap::from([("CONFIG_LIB_TEST_MUSICVOLUME".to_string(), "25%".to_string())]);
+        let lookup = |variable_name: &str| environment.get(variable_name).cloned();
+        device_config.apply_env_overrides_with("CONFIG_LIB_TEST_", lookup);
+        assert_eq!(
+            device_config
+                .get_parameter_value_with_source("musicvolume")
+                .unwrap(),
+            ("25%".to_string(), ValueSource::Environment)
+        );
+
+        device_config
+            .apply_cli_overrides(&["musicvolume=75%".to_string()])
+            .unwrap();
+        device_config.apply_env_overrides_with("CONFIG_LIB_TEST_", lookup);
+        assert_eq!(
+            device_config
+                .get_parameter_value_with_source("musicvolume")
+                .unwrap(),
+            ("75%".to_string(), ValueSource::CommandLine)
+        );
+
+        assert!(
+            device_config
+                .apply_cli_overrides(&["musicvolume".to_string()])
+                .is_err()
+        );
+        assert!(
+            device_config
+                .apply_cli_overrides(&["unknown=1".to_string()])
+                .is_err()
+        );
+
+        device_config.save_parameters_values().unwrap();
+
+        let device_config = DeviceConfig::create_parameter_list(&scheme_path).unwrap();
+        assert_eq!(
+            device_config
+                .get_parameter_value_with_source("musicvolume")
+                .unwrap(),
+            (scheme_value, ValueSource::Scheme)
+        );
+
+        std::fs::remove_file(&scheme_path).unwrap();
+    }
+
+    #[test]
+    fn test_constraints_validation() {
+        let scheme_path = crate::copy_example_scheme("constraints");
+        let mut device_config = DeviceConfig::create_parameter_list(&scheme_path).unwrap();
+
+        device_config
+            .set_parameter_value("groupnumber", "0".to_string())
+            .unwrap();
+        device_config
+            .set_parameter_value("loadcapacity", "1600кг 21чел.".to_string())
+            .unwrap();
+
+        let error = device_config.validate().unwrap_err();
+        assert!(error.contains("groupnumber = 0"));
+        assert!(error.contains("loadcapacity = 1600кг 21чел."));
+        assert!(matches!(
+            device_config.save_parameters_values(),
+            Err(SaveError::Invalid(_))
+        ));
+
+        // Переопределение не сохраняется в схему, поэтому не разрешает сохранение
+        device_config
+            .set_parameter_override("groupnumber", "1".to_string(), ValueSource::CommandLine)
+            .unwrap();
+        assert!(device_config.validate().is_ok());
+        assert!(matches!(
+            device_config.save_parameters_values(),
+            Err(SaveError::Invalid(_))
+        ));
+
+        device_config
+            .set_parameter_value("loadcapacity", "630кг 8чел.".to_string())
+            .unwrap();
+        assert!(device_config.validate().is_ok());
+
+        // ...и не запрещает сохранение допустимых значений схемы
+        device_config
+            .set_parameter_override("groupnumber", "0".to_string(), ValueSource::CommandLine)
+            .unwrap();
+        device_config
+            .set_parameter_override(
+                "loadcapacity",
+                "1600кг 21чел.".to_string(),
+                ValueSource::CommandLine,
+            )
+            .unwrap();
+        assert!(device_config.validate().is_err());
+        assert!(device_config.save_parameters_values().is_ok());
+
+        device_config
+            .set_parameter_override("soundvolume", "33%".to_string(), ValueSource::CommandLine)
+            .unwrap();
+        assert!(
+            device_config
+                .validate()
+                .unwrap_err()
+                .contains("Parameter soundvolume has invalid value 33%")
+        );
+
+        std::fs::remove_file(&scheme_path).unwrap();
+    }
+
+    #[test]
+    fn test_value_aliases_and_tolerant_matching() {
+        let mut device_config =
+            DeviceConfig::create_parameter_list("examples/simple_config.toml").unwrap();
+
+        device_config
+            .set_parameter_value("musicvolume", "off".to_string())
+            .unwrap();
+        assert_eq!(
+            device_config.get_parameter_value("musicvolume").unwrap(),
+            "0%"
+        );
+
+        assert!(
+            device_config
+                .set_parameter_value("soundvolume", "100".to_string())
+                .is_err()
+        );
+
+        device_config.set_tolerant_matching(true);
+        device_config
+            .set_parameter_value("soundvolume", " 100 ".to_string())
+            .unwrap();
+        assert_eq!(
+            device_config.get_parameter_value("soundvolume").unwrap(),
+            "100%"
+        );
+
+        device_config
+            .set_parameter_value("musicvolume", "MAX".to_string())
+            .unwrap();
+        assert_eq!(
+            device_config.get_parameter_value("musicvolume").unwrap(),
+            "100%"
+        );
+
+        device_config
+            .set_parameter_value("loadcapacity", "400КГ 5ЧЕЛ.".to_string())
+            .unwrap();
+        assert_eq!(
+            device_config.get_parameter_value("loadcapacity").unwrap(),
+            "400кг 5чел."
+        );
+
+        assert_eq!(
+            device_config
+                .resolve_parameter_value("soundvolume", "25 %")
+                .unwrap(),
+            "25%"
+        );
+        assert!(
+            device_config
+                .resolve_parameter_value("soundvolume", "30")
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_deprecated_parameter_keys() {
+        let mut device_config =
+            DeviceConfig::create_parameter_list("examples/simple_config.toml").unwrap();
+
+        let names = device_config.get_parameters_names().unwrap();
+        assert!(names.contains(&"bgm_volume".to_string()));
+        assert!(!names.contains(&"musicvolume".to_string()));
+
+        assert!(device_config.has_parameter("musicvolume"));
+        assert_eq!(
+            device_config.resolve_parameter_key("musicvolume").unwrap(),
+            "bgm_volume"
+        );
+
+        device_config
+            .set_parameter_value("musicvolume", "25%".to_string())
+            .unwrap();
+        assert_eq!(
+            device_config.get_parameter_value("bgm_volume").unwrap(),
+            "25%"
+        );
+        assert_eq!(
+            device_config
+                .get_parameter_deprecation("musicvolume")
+                .unwrap(),
+            None
+        );
+        assert!(device_config.resolve_parameter_key("unknown").is_err());
+
+        // Предупреждение об устаревшем имени выводится однократно
+        device_config.get_parameter_info("musicvolume").unwrap();
+        assert_eq!(
+            *device_config.warned_key_aliases.lock().unwrap(),
+            HashSet::from(["musicvolume".to_string()])
+        );
+
+        // Конфигурация доступна из разных потоков (SharedConfig)
+        fn assert_sync<T: Sync>(_: &T) {}
+        assert_sync(&device_config);
+    }
+
+    #[test]
+    fn test_wire_encoding() {
+        let floors: Vec<String> = (0..300).map(|floor| format!("\"{floor}\"")).collect();
+        let scheme = |encoding: &str| {
+            format!(
+                "[floor]\nname = \"Этаж\"\nencoding = \"{encoding}\"\npossible_values = [{}]\ndefault = \"0\"\ncurrent = \"299\"\n",
+                floors.join(", ")
+            )
+        };
+        let path =
+            std::env::temp_dir().join(format!("config_lib_encoding_{}.toml", std::process::id()));
+        let path = path.to_str().unwrap();
+
+        std::fs::write(path, scheme("u8")).unwrap();
+        assert!(DeviceConfig::create_parameter_list(path).is_err());
+
+        std::fs::write(path, scheme("u16")).unwrap();
+        let mut device_config = DeviceConfig::create_parameter_list(path).unwrap();
+        assert_eq!(
+            device_config
+                .get_parameter_index_using_value("floor")
+                .unwrap(),
+            299
+        );
+        assert_eq!(
+            device_config.get_parameter_wire_value("floor").unwrap(),
+            "299"
+        );
+        device_config
+            .set_parameter_value_from_wire("floor", "256")
+            .unwrap();
+        assert_eq!(device_config.get_parameter_value("floor").unwrap(), "256");
+        assert!(
+            device_config
+                .set_parameter_value_from_wire("floor", "70000")
+                .is_err()
+        );
+        assert!(
+            device_config
+                .set_parameter_value_using_index("floor", 300)
+                .is_err()
+        );
+
+        std::fs::write(path, scheme("label")).unwrap();
+        let mut device_config = DeviceConfig::create_parameter_list(path).unwrap();
+        device_config
+            .set_parameter_value_from_wire("floor", "17")
+            .unwrap();
+        assert_eq!(
+            device_config.get_parameter_wire_value("floor").unwrap(),
+            "17"
+        );
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_change_events() {
+        let scheme_path = crate::copy_example_scheme("change_events");
+        let mut device_config = DeviceConfig::create_parameter_list(&scheme_path).unwrap();
+        let events = device_config.subscribe();
+
+        device_config
+            .set_parameter_value("soundvolume", "25%".to_string())
+            .unwrap();
+        device_config
+            .set_parameter_value("soundvolume", "25%".to_string())
+            .unwrap();
+        device_config.reset_parameter_value("soundvolume").unwrap();
+        device_config
+            .set_parameter_override("soundvolume", "75%".to_string(), ValueSource::CommandLine)
+            .unwrap();
+        device_config
+            .set_parameter_value_from_wire("groupnumber", "5")
+            .unwrap();
+
+        // Изменения копии не отправляются подписчикам оригинала
+        let mut device_config_copy = device_config.clone();
+        device_config_copy
+            .set_parameter_value("groupnumber", "7".to_string())
+            .unwrap();
+
+        device_config.reload_parameters_values().unwrap();
+
+        let received: Vec<(String, String, String, ChangeSource)> = events
+            .try_iter()
+            .map(|event| (event.key, event.old_value, event.new_value, event.source))
+            .collect();
+
+        assert_eq!(
+            received[0],
+            (
+                "soundvolume".into(),
+                "100%".into(),
+                "25%".into(),
+                ChangeSource::Set
+            )
+        );
+        assert_eq!(
+            received[1],
+            (
+                "soundvolume".into(),
+                "25%".into(),
+                "50%".into(),
+                ChangeSource::Reset
+            )
+        );
+        assert_eq!(
+            received[2],
+            (
+                "soundvolume".into(),
+                "50%".into(),
+                "75%".into(),
+                ChangeSource::Override(ValueSource::CommandLine)
+            )
+        );
+        assert_eq!(received[3].0, "groupnumber");
+        assert_eq!(received[3].2, "5");
+        assert_eq!(received[3].3, ChangeSource::Device);
+        assert!(
+            received[4..]
+                .iter()
+                .all(|event| event.3 == ChangeSource::Reload)
+        );
+        assert!(received[4..].iter().all(|event| event.0 == "groupnumber"));
+
+        std::fs::remove_file(&scheme_path).unwrap();
+    }
+
+    #[test]
+    fn test_parameters_info() {
+        let device_config =
+            DeviceConfig::create_parameter_list("examples/simple_config.toml").unwrap();
+
+        let keys: Vec<String> = device_config
+            .parameters()
+            .map(|parameter| parameter.key)
+            .collect();
+        assert_eq!(keys, device_config.get_parameters_names().unwrap());
+
+        let info = device_config.get_parameter_info("musicvolume").unwrap();
+        assert_eq!(info.key, "bgm_volume");
+        assert_eq!(info.key_aliases, vec!["musicvolume"]);
+        assert_eq!(info.unit.as_deref(), Some("%"));
+        assert_eq!(info.source, ValueSource::Scheme);
+        assert_eq!(info.possible_values.len(), 5);
+        assert_eq!(info.encoding, WireEncoding::IndexU8);
+    }
+
+    #[test]
+    fn test_parameter_metadata() {
+        let device_config =
+            DeviceConfig::create_parameter_list("examples/simple_config.toml").unwrap();
+
+        assert!(
+            device_config
+                .get_parameter_help("groupnumber")
+                .unwrap()
+                .is_some()
+        );
+        assert_eq!(
+            device_config.get_parameter_unit("soundvolume").unwrap(),
+            Some("%".to_string())
+        );
+        assert!(
+            device_config
+                .get_parameter_warning("loadcapacity")
+                .unwrap()
+                .is_some()
+        );
+        assert_eq!(
+            device_config.get_parameter_warning("musicvolume").unwrap(),
+            None
+        );
+        assert!(device_config.get_parameter_unit("unknown").is_err());
+    }
 }
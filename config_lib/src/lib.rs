@@ -1,2 +1,23 @@
+pub mod audit_log;
+pub mod change_events;
+mod constraint;
 pub mod device_config;
+pub mod merge;
+pub mod scheme_docs;
+pub mod scheme_refresh;
+pub mod shared_config;
 mod toml_parser;
+pub mod unit_store;
+
+/// Копия файла-схемы из примеров для тестов, изменяющих схему
+/// Каждый тест работает со своей копией: тесты выполняются параллельно
+#[cfg(test)]
+pub(crate) fn copy_example_scheme(name: &str) -> String {
+    let path = std::env::temp_dir().join(format!(
+        "config_lib_scheme_{}_{}.toml",
+        name,
+        std::process::id()
+    ));
+    std::fs::copy("examples/simple_config.toml", &path).unwrap();
+    path.to_str().unwrap().to_string()
+}
@@ -0,0 +1,261 @@
+//! # Трехстороннее слияние значений параметров
+//!
+//! > База - значения на момент последней синхронизации с интерфейсной платой (снимок),
+//! > локальные значения - из файла-схемы, значения устройства - полученные от платы.
+//! > Изменения, сделанные только на одной стороне, переносятся автоматически;
+//! > параметр, измененный на обеих сторонах по-разному, - конфликт.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::unit_store::{read_values_file, write_values_file};
+
+/// Конфликт: значение параметра изменено и локально, и на устройстве
+#[derive(Debug, Clone, PartialEq)]
+pub struct MergeConflict {
+    /// Имя параметра
+    pub key: String,
+    /// Значение на момент последней синхронизации (`None` - параметр отсутствовал)
+    pub base: Option<String>,
+    /// Локальное значение
+    pub local: String,
+    /// Значение устройства
+    pub device: String,
+}
+
+/// Способ разрешения конфликта
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictResolution {
+    /// Оставить локальное значение
+    Local,
+    /// Принять значение устройства
+    Device,
+}
+
+impl MergeConflict {
+    /// ## Значение параметра при выбранном способе разрешения конфликта
+    pub fn resolve(&self, resolution: ConflictResolution) -> &str {
+        match resolution {
+            ConflictResolution::Local => &self.local,
+            ConflictResolution::Device => &self.device,
+        }
+    }
+}
+
+/// Результат слияния
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MergeResult {
+    /// Значения параметров без конфликтов
+    pub values: BTreeMap<String, String>,
+    /// Параметры, значения которых приняты от устройства
+    pub from_device: Vec<String>,
+    /// Параметры, локальные изменения которых сохранены
+    pub from_local: Vec<String>,
+    /// Конфликты, требующие разрешения
+    pub conflicts: Vec<MergeConflict>,
+    /// Значения, полученные от устройства, - база следующего слияния.
+    /// Локальные изменения не передаются устройству при слиянии и в базу не входят
+    pub device: BTreeMap<String, String>,
+}
+
+impl MergeResult {
+    /// ## Разрешение конфликта параметра `key`: значение переносится в `values`
+    pub fn resolve_conflict(
+        &mut self,
+        key: &str,
+        resolution: ConflictResolution,
+    ) -> Result<(), String> {
+        let Some(position) = self
+            .conflicts
+            .iter()
+            .position(|conflict| conflict.key == key)
+        else {
+            return Err(format!("Parameter {key} has no merge conflict"));
+        };
+        let conflict = self.conflicts.remove(position);
+        match resolution {
+            ConflictResolution::Device => self.from_device.push(key.to_string()),
+            ConflictResolution::Local => self.from_local.push(key.to_string()),
+        }
+        self.values.insert(
+            conflict.key.clone(),
+            conflict.resolve(resolution).to_string(),
+        );
+        Ok(())
+    }
+}
+
+/// ## Трехстороннее слияние значений `local` и `device` относительно `base`
+/// Параметр, присутствующий только на одной стороне, берется с этой стороны
+pub fn merge_values(
+    base: &BTreeMap<String, String>,
+    local: &BTreeMap<String, String>,
+    device: &BTreeMap<String, String>,
+) -> MergeResult {
+    let mut keys: Vec<&String> = local.keys().chain(device.keys()).collect();
+    keys.sort();
+    keys.dedup();
+
+    let mut result = MergeResult {
+        device: device.clone(),
+        ..Default::default()
+    };
+    for key in keys {
+        let base_value = base.get(key);
+        let merged = match (local.get(key), device.get(key)) {
+            (Some(local_value), Some(device_value)) if local_value == device_value => local_value,
+            (Some(local_value), Some(device_value)) if Some(local_value) == base_value => {
+                result.from_device.push(key.clone());
+                device_value
+            }
+            (Some(local_value), Some(device_value)) if Some(device_value) == base_value => {
+                result.from_local.push(key.clone());
+                local_value
+            }
+            (Some(local_value), Some(device_value)) => {
+                result.conflicts.push(MergeConflict {
+                    key: key.clone(),
+                    base: base_value.cloned(),
+                    local: local_value.clone(),
+                    device: device_value.clone(),
+                });
+                continue;
+            }
+            (Some(local_value), None) => local_value,
+            (None, Some(device_value)) => {
+                result.from_device.push(key.clone());
+                device_value
+            }
+            (None, None) => continue,
+        };
+        result.values.insert(key.clone(), merged.clone());
+    }
+    result
+}
+
+/// ## Сохранение снимка значений после синхронизации (база для следующего слияния)
+pub fn save_sync_snapshot(path: &str, values: &BTreeMap<String, String>) -> Result<(), String> {
+    write_values_file(Path::new(path), values)
+}
+
+/// ## Чтение снимка значений последней синхронизации
+/// Возвращает `None`, если синхронизация еще не выполнялась (файл отсутствует)
+pub fn load_sync_snapshot(path: &str) -> Result<Option<BTreeMap<String, String>>, String> {
+    if !Path::new(path).is_file() {
+        return Ok(None);
+    }
+    read_values_file(Path::new(path))
+        .map(Some)
+        .map_err(|e| format!("Unable to read sync snapshot {path}: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_values(values: &[(&str, &str)]) -> BTreeMap<String, String> {
+        values
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_merge_values() {
+        let base = create_values(&[
+            ("groupnumber", "1"),
+            ("soundvolume", "50%"),
+            ("musicvolume", "0%"),
+            ("loadcapacity", "400кг 5чел."),
+        ]);
+        let local = create_values(&[
+            ("groupnumber", "2"),
+            ("soundvolume", "50%"),
+            ("musicvolume", "25%"),
+            ("loadcapacity", "630кг 8чел."),
+        ]);
+        let device = create_values(&[
+            ("groupnumber", "1"),
+            ("soundvolume", "75%"),
+            ("musicvolume", "25%"),
+            ("loadcapacity", "800кг 10чел."),
+        ]);
+
+        let mut result = merge_values(&base, &local, &device);
+
+        assert_eq!(result.values["groupnumber"], "2");
+        assert_eq!(result.values["soundvolume"], "75%");
+        assert_eq!(result.values["musicvolume"], "25%");
+        assert_eq!(result.from_local, vec!["groupnumber"]);
+        assert_eq!(result.from_device, vec!["soundvolume"]);
+        assert_eq!(
+            result.conflicts,
+            vec![MergeConflict {
+                key: "loadcapacity".to_string(),
+                base: Some("400кг 5чел.".to_string()),
+                local: "630кг 8чел.".to_string(),
+                device: "800кг 10чел.".to_string(),
+            }]
+        );
+
+        result
+            .resolve_conflict("loadcapacity", ConflictResolution::Device)
+            .unwrap();
+        assert!(result.conflicts.is_empty());
+        assert_eq!(result.values["loadcapacity"], "800кг 10чел.");
+        assert!(
+            result
+                .resolve_conflict("loadcapacity", ConflictResolution::Local)
+                .is_err()
+        );
+
+        let mut result = merge_values(&base, &local, &device);
+        result
+            .resolve_conflict("loadcapacity", ConflictResolution::Local)
+            .unwrap();
+        assert_eq!(result.values["loadcapacity"], "630кг 8чел.");
+        assert_eq!(result.from_local, vec!["groupnumber", "loadcapacity"]);
+        assert_eq!(result.device, device);
+    }
+
+    #[test]
+    fn test_local_change_survives_repeated_pull() {
+        let path = std::env::temp_dir().join(format!(
+            "config_lib_snapshot_pull_{}.toml",
+            std::process::id()
+        ));
+        let path = path.to_str().unwrap();
+
+        let device = create_values(&[("groupnumber", "1"), ("soundvolume", "50%")]);
+        save_sync_snapshot(path, &device).unwrap();
+        let local = create_values(&[("groupnumber", "2"), ("soundvolume", "50%")]);
+
+        // Локальное изменение не передано устройству: после каждого pull оно сохраняется
+        for _ in 0..2 {
+            let base = load_sync_snapshot(path).unwrap().unwrap();
+            let result = merge_values(&base, &local, &device);
+            assert!(result.conflicts.is_empty());
+            assert_eq!(result.from_local, vec!["groupnumber"]);
+            assert_eq!(result.values, local);
+            save_sync_snapshot(path, &result.device).unwrap();
+        }
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_sync_snapshot() {
+        let path =
+            std::env::temp_dir().join(format!("config_lib_snapshot_{}.toml", std::process::id()));
+        let path = path.to_str().unwrap();
+        let _ = std::fs::remove_file(path);
+
+        assert_eq!(load_sync_snapshot(path).unwrap(), None);
+
+        let values = create_values(&[("groupnumber", "1"), ("soundvolume", "50%")]);
+        save_sync_snapshot(path, &values).unwrap();
+        assert_eq!(load_sync_snapshot(path).unwrap(), Some(values));
+
+        std::fs::remove_file(path).unwrap();
+    }
+}
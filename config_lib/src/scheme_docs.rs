@@ -0,0 +1,178 @@
+//! # Генерация документации по файлу-схеме
+//!
+//! > JSON Schema - для проверки файлов со значениями параметров во внешних редакторах и валидаторах.
+//! > Markdown - справочник по параметрам для интеграторов.
+
+use serde_json::{Map, Value, json};
+use std::path::Path;
+
+use crate::device_config::DeviceConfig;
+
+/// Версия спецификации JSON Schema
+const JSON_SCHEMA_DIALECT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+/// ## Генерация JSON Schema для файла со значениями параметров вида `key = "value"`
+/// Синонимы значений (`aliases`) входят в список допустимых значений,
+/// прежние имена параметров (`aliases_for_key`) описываются как устаревшие свойства
+pub fn generate_json_schema(config: &DeviceConfig) -> Result<String, String> {
+    let mut properties = Map::new();
+
+    for key in config.get_parameters_names()? {
+        let mut property = Map::new();
+        property.insert(
+            "title".into(),
+            json!(config.get_parameter_description(&key)?),
+        );
+        if let Some(help) = config.get_parameter_help(&key)? {
+            property.insert("description".into(), json!(help));
+        }
+        property.insert("type".into(), json!("string"));
+        let mut values = config.get_parameter_possible_values(&key)?;
+        for (_, aliases) in config.get_parameter_value_aliases(&key)? {
+            values.extend(aliases);
+        }
+        property.insert("enum".into(), json!(values));
+        if let Some(default) = config.get_parameter_default(&key)? {
+            property.insert("default".into(), json!(default));
+        }
+        if let Some(unit) = config.get_parameter_unit(&key)? {
+            property.insert("x-unit".into(), json!(unit));
+        }
+        if config.get_parameter_deprecation(&key)?.is_some() {
+            property.insert("deprecated".into(), json!(true));
+        }
+
+        for alias in config.get_parameter_key_aliases(&key)? {
+            let mut alias_property = property.clone();
+            alias_property.insert(
+                "description".into(),
+                json!(format!("Прежнее имя параметра {key}")),
+            );
+            alias_property.insert("deprecated".into(), json!(true));
+            properties.insert(alias, Value::Object(alias_property));
+        }
+        properties.insert(key, Value::Object(property));
+    }
+
+    let schema = json!({
+        "$schema": JSON_SCHEMA_DIALECT,
+        "title": get_scheme_title(config),
+        "type": "object",
+        "properties": properties,
+        "additionalProperties": false,
+    });
+
+    serde_json::to_string_pretty(&schema).map_err(|e| e.to_string())
+}
+
+/// ## Генерация справочника по параметрам в формате Markdown
+pub fn generate_markdown(config: &DeviceConfig) -> Result<String, String> {
+    let mut document = format!("# Параметры `{}`\n", get_scheme_title(config));
+
+    for key in config.get_parameters_names()? {
+        document.push_str(&format!(
+            "\n## `{}` — {}\n\n",
+            key,
+            config.get_parameter_description(&key)?
+        ));
+
+        if let Some(help) = config.get_parameter_help(&key)? {
+            document.push_str(&format!("{help}\n\n"));
+        }
+
+        let possible_values = config
+            .get_parameter_possible_values(&key)?
+            .iter()
+            .map(|value| format!("`{value}`"))
+            .collect::<Vec<String>>()
+            .join(", ");
+        document.push_str(&format!("- Допустимые значения: {possible_values}\n"));
+
+        if let Some(default) = config.get_parameter_default(&key)? {
+            document.push_str(&format!("- Значение по умолчанию: `{default}`\n"));
+        }
+        if let Some(unit) = config.get_parameter_unit(&key)? {
+            document.push_str(&format!("- Единица измерения: {unit}\n"));
+        }
+        for (value, aliases) in config.get_parameter_value_aliases(&key)? {
+            let aliases = aliases
+                .iter()
+                .map(|alias| format!("`{alias}`"))
+                .collect::<Vec<String>>()
+                .join(", ");
+            document.push_str(&format!("- Синонимы `{value}`: {aliases}\n"));
+        }
+        let key_aliases = config.get_parameter_key_aliases(&key)?;
+        if !key_aliases.is_empty() {
+            let key_aliases = key_aliases
+                .iter()
+                .map(|alias| format!("`{alias}`"))
+                .collect::<Vec<String>>()
+                .join(", ");
+            document.push_str(&format!("- Прежние имена: {key_aliases}\n"));
+        }
+        if let Some(warning) = config.get_parameter_warning(&key)? {
+            document.push_str(&format!("- ⚠ {warning}\n"));
+        }
+        if let Some(deprecation) = config.get_parameter_deprecation(&key)? {
+            document.push_str(&format!("- Устарел: {deprecation}\n"));
+        }
+    }
+
+    Ok(document)
+}
+
+/// Получение заголовка документации - имени файла-схемы
+fn get_scheme_title(config: &DeviceConfig) -> String {
+    let path = config.get_path_to_scheme();
+    Path::new(&path)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or(&path)
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_json_schema() {
+        let config = DeviceConfig::create_parameter_list("examples/simple_config.toml").unwrap();
+        let schema: Value = serde_json::from_str(&generate_json_schema(&config).unwrap()).unwrap();
+
+        assert_eq!(schema["title"], "simple_config.toml");
+        assert_eq!(schema["properties"]["soundvolume"]["x-unit"], "%");
+        assert_eq!(schema["properties"]["soundvolume"]["default"], "50%");
+        assert_eq!(
+            schema["properties"]["groupnumber"]["enum"]
+                .as_array()
+                .unwrap()
+                .len(),
+            16
+        );
+
+        // Файлы значений с прежними именами параметров и синонимами значений допустимы
+        let values = schema["properties"]["bgm_volume"]["enum"]
+            .as_array()
+            .unwrap();
+        assert!(values.contains(&json!("100%")));
+        assert!(values.contains(&json!("max")));
+        assert_eq!(schema["properties"]["musicvolume"]["deprecated"], true);
+        assert_eq!(
+            schema["properties"]["musicvolume"]["enum"],
+            schema["properties"]["bgm_volume"]["enum"]
+        );
+    }
+
+    #[test]
+    fn test_generate_markdown() {
+        let config = DeviceConfig::create_parameter_list("examples/simple_config.toml").unwrap();
+        let markdown = generate_markdown(&config).unwrap();
+
+        assert!(markdown.starts_with("# Параметры `simple_config.toml`"));
+        assert!(markdown.contains("## `bgm_volume` — Громкость музыки"));
+        assert!(markdown.contains("- Прежние имена: `musicvolume`"));
+        assert!(markdown.contains("- Синонимы `100%`: `max`"));
+    }
+}
@@ -0,0 +1,450 @@
+//! # Обновление файла-схемы по описанию параметров от интерфейсной платы
+//!
+//! > Плата сообщает список параметров, их кодировку, количество значений и значение по умолчанию.
+//! > Названия, подписи значений (`possible_values`), текущие значения и прочие поля схемы сохраняются,
+//! > если они не противоречат описанию от платы.
+
+use log::warn;
+use toml_edit::{Array, DocumentMut, Item, Table, value};
+
+use crate::device_config::WireEncoding;
+use crate::toml_parser::CONSTRAINTS_KEY;
+
+/// Количество неизмененных строк, выводимых вокруг изменения в `diff_schemes`
+const DIFF_CONTEXT_LINES: usize = 2;
+
+/// Массивы длиннее этого значения записываются по одному элементу на строку
+const INLINE_ARRAY_MAX_LEN: usize = 5;
+
+/// Значения параметра, известные интерфейсной плате
+#[derive(Debug, Clone, PartialEq)]
+pub enum DescribedValues {
+    /// Для индексных кодировок - только количество значений, подписи берутся из схемы
+    Count(usize),
+    /// Для кодировки `label` - сами значения
+    Labels(Vec<String>),
+}
+
+/// Описание параметра, полученное от интерфейсной платы
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParameterDescription {
+    /// Имя параметра в ПО интерфейсной платы
+    pub key: String,
+    /// Способ передачи значения
+    pub encoding: WireEncoding,
+    /// Допустимые значения
+    pub values: DescribedValues,
+    /// Значение по умолчанию в кодировке параметра (индекс или само значение)
+    pub default: Option<String>,
+}
+
+/// ## Построение файла-схемы по описанию параметров `descriptions`
+/// * `scheme` - содержимое существующей схемы (пустая строка - новая схема)
+///
+/// Параметр схемы соответствует описанию платы по имени или одному из прежних имен (`aliases_for_key`).
+/// Параметры, отсутствующие в описании платы, удаляются из схемы вместе с ограничениями (`[[constraints]]`),
+/// в которых они упоминаются; новые параметры добавляются в конец.
+/// Возвращает содержимое обновленной схемы
+pub fn refresh_scheme(
+    scheme: &str,
+    descriptions: &[ParameterDescription],
+) -> Result<String, String> {
+    let mut document = scheme.parse::<DocumentMut>().map_err(|e| e.to_string())?;
+
+    // Имя параметра в схеме для каждого описания платы
+    let mut scheme_keys = Vec::new();
+    for description in descriptions {
+        let scheme_key = if document.get(&description.key).is_some_and(Item::is_table) {
+            Some(description.key.clone())
+        } else {
+            document
+                .iter()
+                .find(|(_, item)| get_key_aliases(item).contains(&description.key))
+                .map(|(key, _)| key.to_string())
+        };
+        if let Some(scheme_key) = &scheme_key
+            && *scheme_key != description.key
+        {
+            warn!(
+                "Parameter {} is reported by the board under its former name {}",
+                scheme_key, description.key
+            );
+        }
+        scheme_keys.push(scheme_key.unwrap_or(description.key.clone()));
+    }
+
+    let obsolete_keys: Vec<String> = document
+        .iter()
+        .filter(|(key, item)| {
+            item.is_table() && !scheme_keys.iter().any(|scheme_key| scheme_key == key)
+        })
+        .map(|(key, _)| key.to_string())
+        .collect();
+    for key in obsolete_keys {
+        warn!("Parameter {key} is not reported by the board, removed from scheme");
+        document.remove(&key);
+    }
+
+    for (description, scheme_key) in descriptions.iter().zip(&scheme_keys) {
+        match document
+            .get_mut(scheme_key)
+            .and_then(|item| item.as_table_mut())
+        {
+            Some(table) => update_parameter_table(table, description)?,
+            None => {
+                let mut table = Table::new();
+                table["name"] = value(description.key.clone());
+                update_parameter_table(&mut table, description)?;
+                table.decor_mut().set_prefix("\n");
+                document.insert(scheme_key, Item::Table(table));
+            }
+        }
+    }
+
+    remove_obsolete_constraints(&mut document);
+
+    Ok(document.to_string())
+}
+
+/// Прежние имена параметра (`aliases_for_key`) из его таблицы
+fn get_key_aliases(item: &Item) -> Vec<String> {
+    item.get("aliases_for_key")
+        .and_then(|item| item.as_array())
+        .map(|array| {
+            array
+                .iter()
+                .filter_map(|value| value.as_str())
+                .map(|value| value.to_string())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Удаление ограничений, в условиях которых упоминаются отсутствующие в схеме параметры
+fn remove_obsolete_constraints(document: &mut DocumentMut) {
+    let mut known_keys = Vec::new();
+    for (key, item) in document.iter().filter(|(_, item)| item.is_table()) {
+        known_keys.push(key.to_string());
+        known_keys.extend(get_key_aliases(item));
+    }
+
+    let Some(constraints) = document
+        .get_mut(CONSTRAINTS_KEY)
+        .and_then(|item| item.as_array_of_tables_mut())
+    else {
+        return;
+    };
+    constraints.retain(|constraint| {
+        let unknown_keys: Vec<&str> = ["when", "forbid", "require"]
+            .iter()
+            .filter_map(|field| constraint.get(field).and_then(|item| item.as_table_like()))
+            .flat_map(|conditions| conditions.iter().map(|(key, _)| key))
+            .filter(|key| !known_keys.iter().any(|known_key| known_key == key))
+            .collect();
+        if !unknown_keys.is_empty() {
+            warn!(
+                "Constraint refers to removed parameters {}, removed from scheme",
+                unknown_keys.join(", ")
+            );
+        }
+        unknown_keys.is_empty()
+    });
+    if constraints.is_empty() {
+        document.remove(CONSTRAINTS_KEY);
+    }
+}
+
+/// Обновление таблицы параметра по описанию от платы
+/// Поля, значения которых не изменились, не перезаписываются (сохраняется форматирование)
+fn update_parameter_table(
+    table: &mut Table,
+    description: &ParameterDescription,
+) -> Result<(), String> {
+    let key = &description.key;
+    let get_string = |table: &Table, field: &str| {
+        table
+            .get(field)
+            .and_then(|item| item.as_str())
+            .map(|value| value.to_string())
+    };
+
+    let old_labels: Vec<String> = table
+        .get("possible_values")
+        .and_then(|item| item.as_array())
+        .map(|array| {
+            array
+                .iter()
+                .filter_map(|value| value.as_str())
+                .map(|value| value.to_string())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let labels = match &description.values {
+        DescribedValues::Labels(labels) => labels.clone(),
+        DescribedValues::Count(count) => {
+            let mut labels: Vec<String> = old_labels.iter().take(*count).cloned().collect();
+            for index in labels.len()..*count {
+                // Подпись для нового значения - его индекс, при совпадении с имеющейся подписью - `#индекс`
+                let label = index.to_string();
+                if labels.contains(&label) {
+                    labels.push(format!("#{index}"));
+                } else {
+                    labels.push(label);
+                }
+            }
+            labels
+        }
+    };
+    if labels.is_empty() {
+        return Err(format!("Parameter {key} has no possible values"));
+    }
+    if labels != old_labels {
+        table["possible_values"] = value(create_array(&labels));
+    }
+
+    let board_default =
+        description
+            .default
+            .as_ref()
+            .and_then(|default| match description.encoding {
+                WireEncoding::Label => Some(default.clone()),
+                _ => default
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|index| labels.get(index).cloned()),
+            });
+    let default = board_default
+        .filter(|default| labels.contains(default))
+        .or(get_string(table, "default").filter(|default| labels.contains(default)))
+        .unwrap_or(labels[0].clone());
+    if get_string(table, "default").as_ref() != Some(&default) {
+        table["default"] = value(default.clone());
+    }
+
+    let current = get_string(table, "current")
+        .filter(|current| labels.contains(current))
+        .unwrap_or(default);
+    if get_string(table, "current").as_ref() != Some(&current) {
+        table["current"] = value(current);
+    }
+
+    let encoding = description.encoding.to_string();
+    let old_encoding = get_string(table, "encoding");
+    if old_encoding.as_ref() != Some(&encoding)
+        && (old_encoding.is_some() || description.encoding != WireEncoding::IndexU8)
+    {
+        table["encoding"] = value(encoding);
+    }
+
+    Ok(())
+}
+
+/// Создание массива строк в стиле схем: короткие - в одну строку, длинные - по элементу на строку
+fn create_array(values: &[String]) -> Array {
+    let mut array: Array = values.iter().map(|value| value.as_str()).collect();
+    if values.len() > INLINE_ARRAY_MAX_LEN {
+        for item in array.iter_mut() {
+            item.decor_mut().set_prefix("\n    ");
+        }
+        array.set_trailing_comma(true);
+        array.set_trailing("\n");
+    }
+    array
+}
+
+/// ## Построчное сравнение двух версий схемы
+/// Строки с `-` удалены, с `+` добавлены; неизмененные строки выводятся только рядом с изменениями.
+/// Пустая строка - версии совпадают
+pub fn diff_schemes(old: &str, new: &str) -> String {
+    let old_lines: Vec<&str> = old.lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+
+    // Длины наибольших общих подпоследовательностей для суффиксов строк
+    let mut lcs = vec![vec![0usize; new_lines.len() + 1]; old_lines.len() + 1];
+    for i in (0..old_lines.len()).rev() {
+        for j in (0..new_lines.len()).rev() {
+            lcs[i][j] = if old_lines[i] == new_lines[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    // Последовательность строк с отметкой: ' ' - без изменений, '-' - удалена, '+' - добавлена
+    let mut lines: Vec<(char, &str)> = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old_lines.len() || j < new_lines.len() {
+        if i < old_lines.len() && j < new_lines.len() && old_lines[i] == new_lines[j] {
+            lines.push((' ', old_lines[i]));
+            i += 1;
+            j += 1;
+        } else if i < old_lines.len() && (j == new_lines.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            lines.push(('-', old_lines[i]));
+            i += 1;
+        } else {
+            lines.push(('+', new_lines[j]));
+            j += 1;
+        }
+    }
+
+    let changed: Vec<usize> = lines
+        .iter()
+        .enumerate()
+        .filter(|(_, (mark, _))| *mark != ' ')
+        .map(|(index, _)| index)
+        .collect();
+
+    let mut diff = Vec::new();
+    let mut last_printed: Option<usize> = None;
+    for (index, (mark, line)) in lines.iter().enumerate() {
+        let is_near_change = changed
+            .iter()
+            .any(|changed| changed.abs_diff(index) <= DIFF_CONTEXT_LINES);
+        if !is_near_change {
+            continue;
+        }
+        if last_printed.is_some_and(|last| index > last + 1) {
+            diff.push("...".to_string());
+        }
+        diff.push(format!("{mark} {line}"));
+        last_printed = Some(index);
+    }
+    diff.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCHEME: &str = r#"# Схема
+[groupnumber]
+name = "Номер в группе"
+possible_values = ["0", "1", "2"]
+default = "0"
+current = "2"
+
+[floorlabel]
+name = "Обозначение этажа"
+possible_values = ["-1", "1", "2"]
+default = "1"
+current = "-1"
+encoding = "label"
+
+[obsolete]
+name = "Устаревший параметр"
+possible_values = ["a", "b"]
+default = "a"
+current = "b"
+"#;
+
+    #[test]
+    fn test_refresh_scheme() {
+        let descriptions = vec![
+            ParameterDescription {
+                key: "groupnumber".to_string(),
+                encoding: WireEncoding::IndexU8,
+                values: DescribedValues::Count(2),
+                default: Some("1".to_string()),
+            },
+            ParameterDescription {
+                key: "floorlabel".to_string(),
+                encoding: WireEncoding::Label,
+                values: DescribedValues::Labels(vec!["-1".into(), "1".into(), "2".into()]),
+                default: Some("1".to_string()),
+            },
+            ParameterDescription {
+                key: "brightness".to_string(),
+                encoding: WireEncoding::IndexU16,
+                values: DescribedValues::Count(3),
+                default: Some("2".to_string()),
+            },
+        ];
+
+        let refreshed = refresh_scheme(SCHEME, &descriptions).unwrap();
+        let document = refreshed.parse::<DocumentMut>().unwrap();
+
+        assert!(refreshed.starts_with("# Схема"));
+        assert!(document.get("obsolete").is_none());
+        // Подписи сохранены, лишнее значение удалено, текущее значение вне диапазона заменено значением по умолчанию
+        assert_eq!(
+            document["groupnumber"]["name"].as_str(),
+            Some("Номер в группе")
+        );
+        assert_eq!(
+            document["groupnumber"]["possible_values"]
+                .as_array()
+                .unwrap()
+                .len(),
+            2
+        );
+        assert_eq!(document["groupnumber"]["default"].as_str(), Some("1"));
+        assert_eq!(document["groupnumber"]["current"].as_str(), Some("1"));
+        assert!(document["groupnumber"].get("encoding").is_none());
+        // Неизмененный параметр не перезаписан
+        assert!(refreshed.contains("[floorlabel]\nname = \"Обозначение этажа\"\npossible_values = [\"-1\", \"1\", \"2\"]\ndefault = \"1\"\ncurrent = \"-1\"\nencoding = \"label\"\n"));
+        // Новый параметр: подписи - индексы значений
+        assert_eq!(document["brightness"]["name"].as_str(), Some("brightness"));
+        assert_eq!(document["brightness"]["current"].as_str(), Some("2"));
+        assert_eq!(document["brightness"]["encoding"].as_str(), Some("u16"));
+    }
+
+    #[test]
+    fn test_refresh_scheme_with_key_aliases_and_constraints() {
+        let scheme = format!(
+            "{SCHEME}\n[bgm_volume]\nname = \"Громкость музыки\"\n\
+             possible_values = [\"0%\", \"50%\", \"100%\"]\ndefault = \"50%\"\ncurrent = \"100%\"\n\
+             aliases_for_key = [\"musicvolume\"]\n\n\
+             [[constraints]]\nwhen = {{ groupnumber = [\"0\"] }}\nforbid = {{ musicvolume = [\"0%\"] }}\n\n\
+             [[constraints]]\nwhen = {{ obsolete = [\"b\"] }}\nrequire = {{ groupnumber = [\"1\"] }}\n"
+        );
+        let descriptions = vec![
+            ParameterDescription {
+                key: "groupnumber".to_string(),
+                encoding: WireEncoding::IndexU8,
+                values: DescribedValues::Count(3),
+                default: Some("0".to_string()),
+            },
+            ParameterDescription {
+                key: "floorlabel".to_string(),
+                encoding: WireEncoding::Label,
+                values: DescribedValues::Labels(vec!["-1".into(), "1".into(), "2".into()]),
+                default: Some("1".to_string()),
+            },
+            ParameterDescription {
+                key: "musicvolume".to_string(),
+                encoding: WireEncoding::IndexU8,
+                values: DescribedValues::Count(3),
+                default: Some("1".to_string()),
+            },
+        ];
+
+        let refreshed = refresh_scheme(&scheme, &descriptions).unwrap();
+        let document = refreshed.parse::<DocumentMut>().unwrap();
+
+        // Параметр, переданный платой под прежним именем, сохраняет таблицу и подписи значений
+        assert!(document.get("musicvolume").is_none());
+        assert_eq!(
+            document["bgm_volume"]["name"].as_str(),
+            Some("Громкость музыки")
+        );
+        assert_eq!(document["bgm_volume"]["current"].as_str(), Some("100%"));
+        // Ограничение с удаленным параметром удалено, ограничение с прежним именем сохранено
+        let constraints = document["constraints"].as_array_of_tables().unwrap();
+        assert_eq!(constraints.len(), 1);
+        assert!(constraints.get(0).unwrap().get("forbid").is_some());
+    }
+
+    #[test]
+    fn test_diff_schemes() {
+        let old = "a\nb\nc\nd\ne\nf\ng\nh\ni\nj";
+        let new = "a\nb\nc\nD\ne\nf\ng\nh\ni\nj\nk";
+
+        assert_eq!(diff_schemes(old, old), "");
+        assert_eq!(
+            diff_schemes(old, new),
+            "  b\n  c\n- d\n+ D\n  e\n  f\n...\n  i\n  j\n+ k"
+        );
+    }
+}
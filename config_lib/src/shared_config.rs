@@ -0,0 +1,204 @@
+//! # Совместный доступ к конфигурации из нескольких потоков
+//!
+//! > Читатели получают неизменяемые снимки значений (`Arc<ConfigSnapshot>`) без блокировки на время чтения.
+//! > Изменения и сохранение выполняются через `SharedConfig::update` и `SharedConfig::save`
+//! > по очереди; после каждого изменения публикуется новый снимок.
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex, MutexGuard, RwLock};
+
+use crate::device_config::DeviceConfig;
+
+/// Неизменяемый снимок действующих значений параметров
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigSnapshot {
+    /// Номер снимка, увеличивается при каждом изменении конфигурации
+    version: u64,
+    /// Действующие значения параметров (`key` - имя параметра)
+    values: BTreeMap<String, String>,
+}
+
+impl ConfigSnapshot {
+    fn new(version: u64, config: &DeviceConfig) -> Result<Self, String> {
+        Ok(ConfigSnapshot {
+            version,
+            values: config.get_parameters_values()?,
+        })
+    }
+
+    /// ## Номер снимка
+    pub fn get_version(&self) -> u64 {
+        self.version
+    }
+
+    /// ## Значение параметра `key` на момент создания снимка
+    pub fn get_parameter_value(&self, key: &str) -> Option<&str> {
+        self.values.get(key).map(|value| value.as_str())
+    }
+
+    /// ## Значения всех параметров на момент создания снимка
+    pub fn get_parameters_values(&self) -> &BTreeMap<String, String> {
+        &self.values
+    }
+}
+
+/// Общее состояние `SharedConfig`
+#[derive(Debug)]
+struct SharedState {
+    /// Конфигурация, изменяемая одним писателем
+    config: Mutex<DeviceConfig>,
+    /// Последний опубликованный снимок
+    snapshot: RwLock<Arc<ConfigSnapshot>>,
+}
+
+/// Потокобезопасный дескриптор конфигурации, клонирование дескриптора не копирует конфигурацию
+#[derive(Debug, Clone)]
+pub struct SharedConfig {
+    state: Arc<SharedState>,
+}
+
+impl SharedConfig {
+    /// ## Создание дескриптора для конфигурации `config`
+    pub fn new(config: DeviceConfig) -> Result<Self, String> {
+        let snapshot = ConfigSnapshot::new(0, &config)?;
+        Ok(SharedConfig {
+            state: Arc::new(SharedState {
+                config: Mutex::new(config),
+                snapshot: RwLock::new(Arc::new(snapshot)),
+            }),
+        })
+    }
+
+    /// ## Получение последнего снимка значений
+    pub fn snapshot(&self) -> Arc<ConfigSnapshot> {
+        match self.state.snapshot.read() {
+            Ok(snapshot) => snapshot.clone(),
+            Err(poisoned) => poisoned.into_inner().clone(),
+        }
+    }
+
+    /// ## Чтение конфигурации (описания, метаданные параметров, проверка ограничений)
+    /// На время выполнения `read` изменения конфигурации ожидают
+    pub fn read<F, R>(&self, read: F) -> R
+    where
+        F: FnOnce(&DeviceConfig) -> R,
+    {
+        read(&self.lock_config())
+    }
+
+    /// ## Изменение конфигурации с публикацией нового снимка
+    /// `change` выполняется над копией конфигурации: копия заменяет конфигурацию и снимок публикуется
+    /// только при успешном выполнении `change`, иначе конфигурация и снимок не изменяются
+    /// и возвращается ошибка `change`
+    pub fn update<F, R>(&self, change: F) -> Result<R, String>
+    where
+        F: FnOnce(&mut DeviceConfig) -> Result<R, String>,
+    {
+        let mut config = self.lock_config();
+        let mut changed = config.clone();
+        changed.take_observers_from(&mut config);
+
+        let version = self.snapshot().get_version() + 1;
+        let result = change(&mut changed).and_then(|result| {
+            let snapshot = ConfigSnapshot::new(version, &changed)?;
+            Ok((result, snapshot))
+        });
+        let (result, snapshot) = match result {
+            Ok(result) => result,
+            Err(e) => {
+                config.take_observers_from(&mut changed);
+                return Err(e);
+            }
+        };
+
+        *config = changed;
+        let snapshot = Arc::new(snapshot);
+        match self.state.snapshot.write() {
+            Ok(mut current) => *current = snapshot,
+            Err(poisoned) => *poisoned.into_inner() = snapshot,
+        }
+
+        Ok(result)
+    }
+
+    /// ## Сохранение значений параметров в файл-схему
+    pub fn save(&self) -> Result<(), String> {
+        Ok(self.lock_config().save_parameters_values()?)
+    }
+
+    /// Захват конфигурации; после паники другого писателя конфигурация остается доступной
+    fn lock_config(&self) -> MutexGuard<'_, DeviceConfig> {
+        match self.state.config.lock() {
+            Ok(config) => config,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshots_across_threads() {
+        let config = DeviceConfig::create_parameter_list("examples/simple_config.toml").unwrap();
+        let shared = SharedConfig::new(config).unwrap();
+
+        assert_eq!(shared.snapshot().get_version(), 0);
+        shared
+            .update(|config| config.set_parameter_value("soundvolume", "100%".to_string()))
+            .unwrap();
+        let initial = shared.snapshot();
+
+        let writer = shared.clone();
+        std::thread::spawn(move || {
+            writer
+                .update(|config| config.set_parameter_value("soundvolume", "25%".to_string()))
+                .unwrap();
+        })
+        .join()
+        .unwrap();
+
+        // Ранее полученный снимок не изменяется
+        assert_eq!(initial.get_parameter_value("soundvolume"), Some("100%"));
+
+        let current = shared.snapshot();
+        assert_eq!(current.get_version(), 2);
+        assert_eq!(current.get_parameter_value("soundvolume"), Some("25%"));
+        assert_eq!(
+            shared.read(|config| config.get_parameter_value("soundvolume").unwrap()),
+            "25%"
+        );
+
+        assert!(
+            shared
+                .update(|config| config.set_parameter_value("soundvolume", "33%".to_string()))
+                .is_err()
+        );
+        let snapshot = shared.snapshot();
+        assert_eq!(snapshot.get_version(), 2);
+        assert_eq!(snapshot.get_parameter_value("soundvolume"), Some("25%"));
+
+        // Возвращается ошибка изменения, частично выполненное изменение не сохраняется
+        let events = shared.update(|config| Ok(config.subscribe())).unwrap();
+        assert_eq!(
+            shared.update(|config| {
+                config.set_parameter_value("soundvolume", "75%".to_string())?;
+                Err::<(), String>("change failed".to_string())
+            }),
+            Err("change failed".to_string())
+        );
+        assert_eq!(shared.snapshot().get_version(), 3);
+        assert_eq!(
+            shared.read(|config| config.get_parameter_value("soundvolume").unwrap()),
+            "25%"
+        );
+
+        // Подписчики сохраняются после замены конфигурации копией
+        while events.try_recv().is_ok() {}
+        shared
+            .update(|config| config.set_parameter_value("soundvolume", "50%".to_string()))
+            .unwrap();
+        assert!(events.try_recv().is_ok());
+    }
+}
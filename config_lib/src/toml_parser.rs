@@ -1,4 +1,10 @@
-use toml_edit::{DocumentMut, Item, Value};
+use std::collections::HashMap;
+use toml_edit::{DocumentMut, Item, TableLike, Value};
+
+use crate::constraint::{Constraint, ValueCondition};
+
+/// Имя массива таблиц с ограничениями на сочетания значений параметров
+pub(crate) const CONSTRAINTS_KEY: &str = "constraints";
 
 #[derive(Debug, Clone)]
 /// Структура для работы с TOML-конфигом
@@ -50,17 +56,15 @@ impl TomlScheme {
 
     /// Получение значения для `key`(подпараметр) у параметра `parameter_name`
     fn get_parameter_value(&self, parameter_name: &str, key: &str) -> Result<Value, String> {
-        if let Some(table) = self.document.get(parameter_name) {
-            if let Some(sub_table) = table.as_table() {
-                if let Some(value) = sub_table.get(key) {
-                    if let Some(value) = value.as_value() {
-                        return Ok(value.clone());
-                    }
-                }
-            }
+        if let Some(table) = self.document.get(parameter_name)
+            && let Some(sub_table) = table.as_table()
+            && let Some(value) = sub_table.get(key)
+            && let Some(value) = value.as_value()
+        {
+            return Ok(value.clone());
         }
 
-        return Err(format!("Unable to get {} value", key));
+        Err(format!("Unable to get {} value", key))
     }
 
     /// Получение строкового значения для `key`(подпараметр) у параметра `parameter_name`
@@ -77,6 +81,80 @@ impl TomlScheme {
         Err(format!("Unable to get {} value as string", key))
     }
 
+    /// Получение необязательного строкового значения для `key`(подпараметр) у параметра `parameter_name`
+    /// Возвращает `None`, если подпараметр не задан
+    pub(crate) fn get_optional_string_value(
+        &self,
+        parameter_name: &str,
+        key: &str,
+    ) -> Result<Option<String>, String> {
+        if !self.has_parameter_key(parameter_name, key) {
+            return Ok(None);
+        }
+        self.get_string_value(parameter_name, key).map(Some)
+    }
+
+    /// Получение необязательного значения - массива строк для `key`(подпараметр) у параметра `parameter_name`
+    /// Возвращает пустой список, если подпараметр не задан
+    pub(crate) fn get_optional_array_value(
+        &self,
+        parameter_name: &str,
+        key: &str,
+    ) -> Result<Vec<String>, String> {
+        if !self.has_parameter_key(parameter_name, key) {
+            return Ok(Vec::new());
+        }
+        self.get_array_value(parameter_name, key)
+    }
+
+    /// Проверка наличия `key`(подпараметр) у параметра `parameter_name`
+    fn has_parameter_key(&self, parameter_name: &str, key: &str) -> bool {
+        self.document
+            .get(parameter_name)
+            .and_then(|table| table.as_table())
+            .is_some_and(|sub_table| sub_table.contains_key(key))
+    }
+
+    /// Получение синонимов значений параметра `parameter_name` (`aliases`, необязательно)
+    /// Формат: `aliases = { "значение" = ["синоним", ...] }`, значение должно входить в `possible_values`
+    pub(crate) fn get_aliases(
+        &self,
+        parameter_name: &str,
+    ) -> Result<Vec<(String, Vec<String>)>, String> {
+        if !self.has_parameter_key(parameter_name, "aliases") {
+            return Ok(Vec::new());
+        }
+
+        let possible_values = self.get_array_value(parameter_name, "possible_values")?;
+        let aliases = self.get_parameter_value(parameter_name, "aliases")?;
+        let Some(aliases) = aliases.as_inline_table() else {
+            return Err("Unable to get aliases value as table".to_string());
+        };
+
+        let mut result = Vec::new();
+        for (canonical, values) in aliases.iter() {
+            if !possible_values.iter().any(|value| value == canonical) {
+                return Err(format!(
+                    "Alias of {parameter_name} refers to unknown value {canonical}"
+                ));
+            }
+            let Some(values) = values.as_array() else {
+                return Err(format!("Unable to get aliases of {canonical} as array"));
+            };
+            let values = values
+                .iter()
+                .map(|value| {
+                    value
+                        .as_str()
+                        .map(|value| value.to_string())
+                        .ok_or(format!("Unable to get alias of {canonical} as string"))
+                })
+                .collect::<Result<Vec<String>, String>>()?;
+            result.push((canonical.to_string(), values));
+        }
+        Ok(result)
+    }
+
     /// Получение значения - массива строк для `key`(подпараметр) у параметра `parameter_name`
     pub(crate) fn get_array_value(
         &self,
@@ -94,6 +172,103 @@ impl TomlScheme {
         Err(format!("Unable to get {} value as array", key))
     }
 
+    /// Получение списка ограничений на сочетания значений параметров (`[[constraints]]`)
+    /// * `key_aliases` - устаревшие имена параметров (`key` - старое имя, `value` - актуальное имя),
+    ///   параметры ограничений, заданные по старому имени, приводятся к актуальному
+    pub(crate) fn get_constraints(
+        &self,
+        key_aliases: &HashMap<String, String>,
+    ) -> Result<Vec<Constraint>, String> {
+        let Some(item) = self.document.get(CONSTRAINTS_KEY) else {
+            return Ok(Vec::new());
+        };
+
+        let Some(array) = item.as_array_of_tables() else {
+            return Err(format!("{CONSTRAINTS_KEY} must be an array of tables"));
+        };
+
+        let mut constraints = Vec::new();
+        for table in array.iter() {
+            let message = match table.get("message") {
+                Some(message) => Some(
+                    message
+                        .as_str()
+                        .ok_or("Unable to get constraint message as string")?
+                        .to_string(),
+                ),
+                None => None,
+            };
+
+            let mut constraint = Constraint {
+                message,
+                when: Self::get_value_conditions(table, "when")?,
+                forbid: Self::get_value_conditions(table, "forbid")?,
+                require: Self::get_value_conditions(table, "require")?,
+            };
+
+            if constraint.forbid.is_empty() && constraint.require.is_empty() {
+                return Err("Constraint must contain forbid or require".to_string());
+            }
+
+            for condition in constraint
+                .when
+                .iter_mut()
+                .chain(constraint.forbid.iter_mut())
+                .chain(constraint.require.iter_mut())
+            {
+                if let Some(actual_key) = key_aliases.get(&condition.key) {
+                    condition.key = actual_key.clone();
+                }
+            }
+
+            for key in constraint.get_keys() {
+                if !self.tables.iter().any(|table| table == key) {
+                    return Err(format!("Constraint refers to unknown parameter {key}"));
+                }
+            }
+
+            constraints.push(constraint);
+        }
+
+        Ok(constraints)
+    }
+
+    /// Получение списка условий вида `{ parameter = ["value", ...] }` из `key` таблицы ограничения
+    fn get_value_conditions(
+        table: &dyn TableLike,
+        key: &str,
+    ) -> Result<Vec<ValueCondition>, String> {
+        let Some(item) = table.get(key) else {
+            return Ok(Vec::new());
+        };
+
+        let Some(conditions) = item.as_table_like() else {
+            return Err(format!("Unable to get constraint {key} as table"));
+        };
+
+        let mut result = Vec::new();
+        for (parameter_name, values) in conditions.iter() {
+            let values = match values.as_value() {
+                Some(Value::String(value)) => vec![value.value().clone()],
+                Some(Value::Array(array)) => array
+                    .iter()
+                    .map(|value| {
+                        value
+                            .as_str()
+                            .map(|value| value.to_string())
+                            .ok_or(format!("Unable to get constraint {key} value as string"))
+                    })
+                    .collect::<Result<Vec<String>, String>>()?,
+                _ => return Err(format!("Unable to get constraint {key} values")),
+            };
+            result.push(ValueCondition {
+                key: parameter_name.to_string(),
+                values,
+            });
+        }
+        Ok(result)
+    }
+
     /// Установка значения `value` для `key`(подпараметр) у параметра `parameter_name`
     pub(crate) fn set_parameter_value(
         &mut self,
@@ -101,25 +276,23 @@ impl TomlScheme {
         key: &str,
         val: Item,
     ) -> Result<(), String> {
-        if let Some(table) = self.document.get_mut(parameter_name) {
-            if let Some(sub_table) = table.as_table() {
-                if let Some(_) = sub_table.get(key) {
-                    self.document[parameter_name][key] = val;
+        if let Some(table) = self.document.get_mut(parameter_name)
+            && let Some(sub_table) = table.as_table()
+            && sub_table.get(key).is_some()
+        {
+            self.document[parameter_name][key] = val;
 
-                    let new_toml = self.document.to_string();
+            let new_toml = self.document.to_string();
 
-                    std::fs::write(&self.path_to_scheme, new_toml)
-                        .expect("Failed to write TOML file");
+            std::fs::write(&self.path_to_scheme, new_toml).expect("Failed to write TOML file");
 
-                    return Ok(());
-                }
-            }
+            return Ok(());
         }
 
-        return Err(format!(
+        Err(format!(
             "Unable to set {}'s {} value to {:?}",
             parameter_name, key, val
-        ));
+        ))
     }
 }
 
@@ -136,7 +309,8 @@ mod tests {
 
     #[test]
     fn test_set_parameter_value() {
-        let mut toml_scheme = TomlScheme::new("examples/simple_config.toml").unwrap();
+        let scheme_path = crate::copy_example_scheme("set_value");
+        let mut toml_scheme = TomlScheme::new(&scheme_path).unwrap();
         toml_scheme
             .set_parameter_value("groupnumber", "current", value("2:1234"))
             .unwrap();
@@ -144,5 +318,71 @@ mod tests {
             .get_string_value("groupnumber", "current")
             .unwrap();
         assert_eq!(value.as_str(), "2:1234");
+
+        std::fs::remove_file(&scheme_path).unwrap();
+    }
+
+    #[test]
+    fn test_get_constraints() {
+        let toml_scheme = TomlScheme::new("examples/simple_config.toml").unwrap();
+        let constraints = toml_scheme.get_constraints(&HashMap::new()).unwrap();
+        assert_eq!(constraints.len(), 1);
+        assert_eq!(
+            constraints[0].get_keys(),
+            vec!["groupnumber", "loadcapacity"]
+        );
+    }
+
+    #[test]
+    fn test_get_constraints_with_key_aliases() {
+        let path = std::env::temp_dir().join(format!(
+            "config_lib_constraint_aliases_{}.toml",
+            std::process::id()
+        ));
+        let path = path.to_str().unwrap();
+        std::fs::write(
+            path,
+            "[bgm_volume]\naliases_for_key = [\"musicvolume\"]\n\n\
+             [soundvolume]\n\n\
+             [[constraints]]\nwhen = { musicvolume = \"100%\" }\nforbid = { soundvolume = \"0%\" }\n",
+        )
+        .unwrap();
+        let toml_scheme = TomlScheme::new(path).unwrap();
+
+        assert!(toml_scheme.get_constraints(&HashMap::new()).is_err());
+
+        let key_aliases = HashMap::from([("musicvolume".to_string(), "bgm_volume".to_string())]);
+        let constraints = toml_scheme.get_constraints(&key_aliases).unwrap();
+        assert_eq!(constraints[0].get_keys(), vec!["bgm_volume", "soundvolume"]);
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_get_optional_string_value() {
+        let toml_scheme = TomlScheme::new("examples/simple_config.toml").unwrap();
+        assert!(
+            toml_scheme
+                .get_optional_string_value("soundvolume", "unit")
+                .unwrap()
+                .is_some()
+        );
+        assert_eq!(
+            toml_scheme
+                .get_optional_string_value("bgm_volume", "warning")
+                .unwrap(),
+            None
+        );
+        assert!(
+            toml_scheme
+                .get_optional_string_value("loadcapacity", "warning")
+                .unwrap()
+                .is_some()
+        );
+        assert!(
+            toml_scheme
+                .get_optional_string_value("soundvolume", "possible_values")
+                .is_err()
+        );
     }
 }
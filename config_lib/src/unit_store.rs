@@ -0,0 +1,240 @@
+//! # Архив настроек устройств
+//!
+//! > Хранит значения параметров нескольких устройств (по одному TOML-файлу на устройство) в одном каталоге.
+//! > Имя файла - идентификатор устройства (например, серийный номер платы): `<unit_id>.toml`
+
+use log::warn;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use toml_edit::{DocumentMut, value};
+
+use crate::change_events::ChangeSource;
+use crate::device_config::DeviceConfig;
+
+/// Расширение файлов со значениями параметров устройств
+const UNIT_FILE_EXTENSION: &str = "toml";
+
+/// Отличие значения параметра у двух устройств
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnitDifference {
+    /// Имя параметра
+    pub key: String,
+    /// Значение у первого устройства (`None` - параметр отсутствует)
+    pub first: Option<String>,
+    /// Значение у второго устройства (`None` - параметр отсутствует)
+    pub second: Option<String>,
+}
+
+/// Архив настроек устройств в каталоге `directory`
+#[derive(Debug, Clone)]
+pub struct UnitStore {
+    directory: PathBuf,
+}
+
+impl UnitStore {
+    /// ## Открытие архива в каталоге `directory`, каталог создается при отсутствии
+    pub fn open(directory: &str) -> Result<Self, String> {
+        std::fs::create_dir_all(directory).map_err(|e| e.to_string())?;
+        Ok(UnitStore {
+            directory: PathBuf::from(directory),
+        })
+    }
+
+    /// ## Получение списка идентификаторов устройств в архиве (в алфавитном порядке)
+    pub fn list_units(&self) -> Result<Vec<String>, String> {
+        let mut units = Vec::new();
+        for entry in std::fs::read_dir(&self.directory).map_err(|e| e.to_string())? {
+            let path = entry.map_err(|e| e.to_string())?.path();
+            if path
+                .extension()
+                .is_some_and(|ext| ext == UNIT_FILE_EXTENSION)
+                && let Some(unit_id) = path.file_stem().and_then(|stem| stem.to_str())
+            {
+                units.push(unit_id.to_string());
+            }
+        }
+        units.sort();
+        Ok(units)
+    }
+
+    /// ## Проверка наличия устройства `unit_id` в архиве
+    pub fn contains_unit(&self, unit_id: &str) -> bool {
+        self.get_unit_path(unit_id)
+            .is_ok_and(|path| Path::new(&path).is_file())
+    }
+
+    /// ## Сохранение текущих значений параметров `config` для устройства `unit_id`
+    /// Сохраняются значения схемы: переопределения (окружение, командная строка) в архив не попадают
+    pub fn save_unit(&self, unit_id: &str, config: &DeviceConfig) -> Result<(), String> {
+        let path = self.get_unit_path(unit_id)?;
+        write_values_file(&path, &config.get_parameters_scheme_values()?)
+    }
+
+    /// ## Получение сохраненных значений параметров устройства `unit_id`
+    pub fn get_unit_values(&self, unit_id: &str) -> Result<BTreeMap<String, String>, String> {
+        let path = self.get_unit_path(unit_id)?;
+        read_values_file(&path).map_err(|e| format!("Unable to read unit {unit_id}: {e}"))
+    }
+
+    /// ## Загрузка сохраненных значений устройства `unit_id` в `config`
+    /// Параметры, отсутствующие в схеме `config`, пропускаются
+    pub fn load_unit(&self, unit_id: &str, config: &mut DeviceConfig) -> Result<(), String> {
+        for (key, parameter_value) in self.get_unit_values(unit_id)? {
+            if !config.has_parameter(&key) {
+                warn!("Unit {unit_id}: parameter {key} is not found in scheme, skipped");
+                continue;
+            }
+            config.set_parameter_value_from(&key, parameter_value, ChangeSource::Import)?;
+        }
+        Ok(())
+    }
+
+    /// ## Сравнение значений параметров устройств `first_unit` и `second_unit`
+    /// Возвращает только отличающиеся параметры
+    pub fn compare_units(
+        &self,
+        first_unit: &str,
+        second_unit: &str,
+    ) -> Result<Vec<UnitDifference>, String> {
+        let first = self.get_unit_values(first_unit)?;
+        let second = self.get_unit_values(second_unit)?;
+
+        let mut keys: Vec<&String> = first.keys().chain(second.keys()).collect();
+        keys.sort();
+        keys.dedup();
+
+        Ok(keys
+            .into_iter()
+            .filter(|key| first.get(*key) != second.get(*key))
+            .map(|key| UnitDifference {
+                key: key.clone(),
+                first: first.get(key).cloned(),
+                second: second.get(key).cloned(),
+            })
+            .collect())
+    }
+
+    /// ## Удаление устройства `unit_id` из архива
+    pub fn remove_unit(&self, unit_id: &str) -> Result<(), String> {
+        std::fs::remove_file(self.get_unit_path(unit_id)?).map_err(|e| e.to_string())
+    }
+
+    /// Получение пути к файлу устройства `unit_id`
+    /// Идентификатор может содержать только латинские буквы, цифры, `-`, `_` и `.`
+    fn get_unit_path(&self, unit_id: &str) -> Result<PathBuf, String> {
+        let is_valid = !unit_id.is_empty()
+            && !unit_id.starts_with('.')
+            && unit_id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+        if !is_valid {
+            return Err(format!("Invalid unit id: {unit_id}"));
+        }
+        Ok(self
+            .directory
+            .join(format!("{unit_id}.{UNIT_FILE_EXTENSION}")))
+    }
+}
+
+/// Запись значений параметров в файл `path` в виде `key = "value"`
+pub(crate) fn write_values_file(
+    path: &Path,
+    values: &BTreeMap<String, String>,
+) -> Result<(), String> {
+    let mut document = DocumentMut::new();
+    for (key, parameter_value) in values {
+        document[key.as_str()] = value(parameter_value);
+    }
+
+    std::fs::write(path, document.to_string()).map_err(|e| e.to_string())
+}
+
+/// Чтение значений параметров из файла `path` вида `key = "value"`
+pub(crate) fn read_values_file(path: &Path) -> Result<BTreeMap<String, String>, String> {
+    let toml_str = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let document = toml_str.parse::<DocumentMut>().map_err(|e| e.to_string())?;
+
+    let mut values = BTreeMap::new();
+    for (key, item) in document.iter() {
+        match item.as_str() {
+            Some(parameter_value) => {
+                values.insert(key.to_string(), parameter_value.to_string());
+            }
+            None => return Err(format!("Unable to get {key} value")),
+        }
+    }
+    Ok(values)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::device_config::ValueSource;
+
+    fn create_test_store(name: &str) -> UnitStore {
+        let directory = std::env::temp_dir().join(format!(
+            "config_lib_unit_store_{}_{}",
+            name,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&directory);
+        UnitStore::open(directory.to_str().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_save_list_and_load_units() {
+        let store = create_test_store("save_load");
+        let mut config =
+            DeviceConfig::create_parameter_list("examples/simple_config.toml").unwrap();
+
+        config
+            .set_parameter_value("groupnumber", "3".to_string())
+            .unwrap();
+        config
+            .set_parameter_value("soundvolume", "25%".to_string())
+            .unwrap();
+        config
+            .set_parameter_override("groupnumber", "9".to_string(), ValueSource::Environment)
+            .unwrap();
+        store.save_unit("SN-0002", &config).unwrap();
+        assert_eq!(
+            store.get_unit_values("SN-0002").unwrap()["groupnumber"],
+            "3"
+        );
+
+        config
+            .set_parameter_value("soundvolume", "75%".to_string())
+            .unwrap();
+        store.save_unit("SN-0001", &config).unwrap();
+
+        assert_eq!(store.list_units().unwrap(), vec!["SN-0001", "SN-0002"]);
+        assert!(store.contains_unit("SN-0001"));
+        assert!(!store.contains_unit("SN-0003"));
+
+        store.load_unit("SN-0002", &mut config).unwrap();
+        assert_eq!(config.get_parameter_value("soundvolume").unwrap(), "25%");
+
+        let differences = store.compare_units("SN-0001", "SN-0002").unwrap();
+        assert_eq!(
+            differences,
+            vec![UnitDifference {
+                key: "soundvolume".to_string(),
+                first: Some("75%".to_string()),
+                second: Some("25%".to_string()),
+            }]
+        );
+
+        store.remove_unit("SN-0001").unwrap();
+        assert_eq!(store.list_units().unwrap(), vec!["SN-0002"]);
+    }
+
+    #[test]
+    fn test_invalid_unit_id() {
+        let store = create_test_store("invalid_id");
+        let config = DeviceConfig::create_parameter_list("examples/simple_config.toml").unwrap();
+
+        assert!(store.save_unit("../escape", &config).is_err());
+        assert!(store.save_unit("", &config).is_err());
+        assert!(store.get_unit_values("missing").is_err());
+    }
+}
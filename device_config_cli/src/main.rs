@@ -0,0 +1,381 @@
+use std::process::ExitCode;
+use std::str::FromStr;
+
+use clap::{Parser, Subcommand};
+use config_lib::audit_log::{AuditLog, AuditQuery, AuditTool, parse_timestamp};
+use config_lib::device_config::{DeviceConfig, SaveError};
+use config_lib::scheme_docs::{generate_json_schema, generate_markdown};
+use log::warn;
+use serde_json::{Value, json};
+
+#[derive(Parser)]
+#[command(author = "MU LLC", name = "device-config", version = "0.1.0", about, long_about = None)]
+struct Args {
+    /// Путь к файлу-схеме TOML
+    #[arg(short = 's', long = "scheme")]
+    scheme: String,
+
+    /// Вывод результата в формате JSON
+    #[arg(short = 'j', long = "json", global = true)]
+    json: bool,
+
+    /// Журнал изменений: сохраненные изменения записываются в него, команда audit читает из него
+    #[arg(short = 'a', long = "audit-log", global = true)]
+    audit_log: Option<String>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Список параметров с текущими значениями
+    List,
+    /// Текущее значение параметра
+    Get {
+        /// Имя параметра
+        key: String,
+    },
+    /// Установка и сохранение значения параметра
+    Set {
+        /// Имя параметра
+        key: String,
+        /// Новое значение (одно из допустимых значений или его синоним)
+        value: String,
+    },
+    /// Список допустимых значений параметра
+    Options {
+        /// Имя параметра
+        key: String,
+    },
+    /// Сброс параметра к значению по умолчанию
+    Reset {
+        /// Имя параметра
+        key: String,
+    },
+    /// Проверка текущих значений и ограничений схемы
+    Validate,
+    /// Генерация документации по параметрам схемы
+    Docs {
+        /// Формат документации: markdown - справочник, json-schema - JSON Schema для файлов значений
+        #[arg(short = 'f', long = "format", default_value = "markdown")]
+        format: DocsFormat,
+        /// Файл для сохранения документации (по умолчанию - stdout)
+        #[arg(short = 'o', long = "output")]
+        output: Option<String>,
+    },
+    /// Просмотр журнала изменений (требуется --audit-log)
+    Audit {
+        /// Имя параметра
+        #[arg(short = 'k', long = "key")]
+        key: Option<String>,
+        /// Начало интервала времени в формате RFC 3339 (например, 2025-03-01T00:00:00Z)
+        #[arg(long = "since")]
+        since: Option<String>,
+        /// Конец интервала времени в формате RFC 3339
+        #[arg(long = "until")]
+        until: Option<String>,
+        /// Путь к файлу-схеме, изменения которой выводятся (журнал может быть общим для нескольких схем)
+        #[arg(long = "scheme-file")]
+        scheme_file: Option<String>,
+    },
+}
+
+/// Ошибки выполнения команды, каждой соответствует свой код завершения
+#[derive(Debug)]
+enum CliError {
+    /// Параметр отсутствует в схеме
+    UnknownParameter(String),
+    /// Значение не входит в список допустимых
+    InvalidValue(String),
+    /// Нарушены ограничения схемы или текущие значения некорректны
+    ValidationFailed(String),
+    /// Ошибка чтения/записи файла-схемы и прочие ошибки
+    Other(String),
+}
+
+impl CliError {
+    /// Код завершения процесса (2 зарезервирован clap для ошибок аргументов)
+    fn exit_code(&self) -> u8 {
+        match self {
+            CliError::Other(_) => 1,
+            CliError::UnknownParameter(_) => 3,
+            CliError::InvalidValue(_) => 4,
+            CliError::ValidationFailed(_) => 5,
+        }
+    }
+
+    fn message(&self) -> &str {
+        match self {
+            CliError::UnknownParameter(message)
+            | CliError::InvalidValue(message)
+            | CliError::ValidationFailed(message)
+            | CliError::Other(message) => message,
+        }
+    }
+}
+
+impl From<String> for CliError {
+    fn from(message: String) -> Self {
+        CliError::Other(message)
+    }
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+
+    env_logger::init();
+
+    match run(&args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            if args.json {
+                println!(
+                    "{}",
+                    json!({ "error": error.message(), "code": error.exit_code() })
+                );
+            } else {
+                eprintln!("device-config> {}", error.message());
+            }
+            ExitCode::from(error.exit_code())
+        }
+    }
+}
+
+/// Выполнение команды `args.command`
+fn run(args: &Args) -> Result<(), CliError> {
+    let mut config = DeviceConfig::create_parameter_list(&args.scheme)?;
+    // Значения вводятся вручную, допускаются вариации написания
+    config.set_tolerant_matching(true);
+    if let Some(path) = &args.audit_log {
+        config.set_audit_log(AuditLog::open(path), AuditTool::Cli);
+    }
+
+    match &args.command {
+        Command::List => {
+            let mut parameters = Vec::new();
+            for key in config.get_parameters_names()? {
+                parameters.push(get_parameter_json(&config, &key)?);
+            }
+            print_output(args.json, Value::Array(parameters), |value| {
+                value
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .map(|parameter| {
+                        format!(
+                            "{} = {} ({})",
+                            parameter["key"].as_str().unwrap_or_default(),
+                            parameter["value"].as_str().unwrap_or_default(),
+                            parameter["name"].as_str().unwrap_or_default()
+                        )
+                    })
+                    .collect::<Vec<String>>()
+                    .join("\n")
+            });
+        }
+        Command::Get { key } => {
+            let key = resolve_key(&config, key)?;
+            let parameter = get_parameter_json(&config, &key)?;
+            print_output(args.json, parameter, |value| {
+                value["value"].as_str().unwrap_or_default().to_string()
+            });
+        }
+        Command::Options { key } => {
+            let key = resolve_key(&config, key)?;
+            let output = json!({
+                "key": key,
+                "value": config.get_parameter_value(&key)?,
+                "options": config.get_parameter_possible_values(&key)?,
+            });
+            print_output(args.json, output, |value| {
+                value["options"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|option| option.as_str())
+                    .collect::<Vec<&str>>()
+                    .join("\n")
+            });
+        }
+        Command::Set { key, value } => {
+            let key = resolve_key(&config, key)?;
+            let value = config
+                .resolve_parameter_value(&key, value)
+                .map_err(CliError::InvalidValue)?;
+            update_parameter(&mut config, &key, value, args.json)?;
+        }
+        Command::Reset { key } => {
+            let key = resolve_key(&config, key)?;
+            let old_value = config.get_parameter_value(&key)?;
+            config
+                .reset_parameter_value(&key)
+                .map_err(CliError::InvalidValue)?;
+            save_parameter(&config, &key, old_value, args.json)?;
+        }
+        Command::Validate => {
+            config.validate().map_err(CliError::ValidationFailed)?;
+            print_output(args.json, json!({ "valid": true }), |_| "OK".to_string());
+        }
+        Command::Docs { format, output } => generate_docs(&config, format, output)?,
+        Command::Audit {
+            key,
+            since,
+            until,
+            scheme_file,
+        } => {
+            let Some(path) = &args.audit_log else {
+                return Err(CliError::Other(
+                    "Audit log path is not set (--audit-log)".to_string(),
+                ));
+            };
+            // Параметр мог быть удален из схемы, тогда имя используется как есть
+            let key = key.as_ref().map(|key| {
+                config
+                    .resolve_parameter_key(key)
+                    .unwrap_or_else(|_| key.clone())
+            });
+            let query = AuditQuery {
+                key,
+                scheme: scheme_file.clone(),
+                since: since.as_deref().map(parse_timestamp).transpose()?,
+                until: until.as_deref().map(parse_timestamp).transpose()?,
+            };
+
+            let mut entries = Vec::new();
+            for entry in AuditLog::open(path).query(&query)? {
+                entries.push(entry.to_json()?);
+            }
+            print_output(args.json, Value::Array(entries), |value| {
+                value
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .map(|entry| {
+                        format!(
+                            "{} {}: {} -> {} ({})",
+                            entry["timestamp"].as_str().unwrap_or_default(),
+                            entry["key"].as_str().unwrap_or_default(),
+                            entry["old"].as_str().unwrap_or_default(),
+                            entry["new"].as_str().unwrap_or_default(),
+                            entry["tool"].as_str().unwrap_or_default()
+                        )
+                    })
+                    .collect::<Vec<String>>()
+                    .join("\n")
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Получение актуального имени параметра `key`
+fn resolve_key(config: &DeviceConfig, key: &str) -> Result<String, CliError> {
+    config
+        .resolve_parameter_key(key)
+        .map_err(CliError::UnknownParameter)
+}
+
+/// Описание параметра `key` в формате JSON
+fn get_parameter_json(config: &DeviceConfig, key: &str) -> Result<Value, CliError> {
+    let (value, source) = config.get_parameter_value_with_source(key)?;
+    Ok(json!({
+        "key": key,
+        "name": config.get_parameter_description(key)?,
+        "value": value,
+        "source": source.to_string(),
+        "unit": config.get_parameter_unit(key)?,
+    }))
+}
+
+/// Установка значения параметра `key` и сохранение схемы
+fn update_parameter(
+    config: &mut DeviceConfig,
+    key: &str,
+    value: String,
+    json_output: bool,
+) -> Result<(), CliError> {
+    let old_value = config.get_parameter_value(key)?;
+    config
+        .set_parameter_value(key, value)
+        .map_err(CliError::InvalidValue)?;
+    save_parameter(config, key, old_value, json_output)
+}
+
+/// Сохранение схемы с проверкой ограничений и вывод изменения параметра `key`
+fn save_parameter(
+    config: &DeviceConfig,
+    key: &str,
+    old_value: String,
+    json_output: bool,
+) -> Result<(), CliError> {
+    config
+        .save_parameters_values()
+        .map_err(|error| match error {
+            SaveError::Invalid(message) => CliError::ValidationFailed(message),
+            SaveError::Failed(message) => CliError::Other(message),
+        })?;
+
+    let output = json!({ "key": key, "old": old_value, "new": config.get_parameter_value(key)? });
+    print_output(json_output, output, |output| {
+        format!(
+            "{}: {} -> {}",
+            key,
+            output["old"].as_str().unwrap_or_default(),
+            output["new"].as_str().unwrap_or_default()
+        )
+    });
+    Ok(())
+}
+
+/// Вывод результата в формате JSON или в текстовом виде (`to_text`)
+fn print_output<F>(json_output: bool, output: Value, to_text: F)
+where
+    F: Fn(&Value) -> String,
+{
+    if json_output {
+        println!("{output}");
+    } else {
+        println!("{}", to_text(&output));
+    }
+}
+
+/// Генерация документации по схеме в формате `format`
+fn generate_docs(
+    config: &DeviceConfig,
+    format: &DocsFormat,
+    output: &Option<String>,
+) -> Result<(), String> {
+    let document = match format {
+        DocsFormat::Markdown => generate_markdown(config)?,
+        DocsFormat::JsonSchema => generate_json_schema(config)?,
+    };
+
+    match output {
+        Some(path) => {
+            std::fs::write(path, document).map_err(|e| e.to_string())?;
+            warn!("device-config> documentation saved to {path}");
+        }
+        None => println!("{document}"),
+    }
+
+    Ok(())
+}
+
+#[derive(Clone, Debug)]
+enum DocsFormat {
+    Markdown,
+    JsonSchema,
+}
+
+impl FromStr for DocsFormat {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "markdown" => Ok(DocsFormat::Markdown),
+            "json-schema" => Ok(DocsFormat::JsonSchema),
+            _ => Err(format!("Unknown documentation format: {s}")),
+        }
+    }
+}
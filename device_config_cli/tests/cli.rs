@@ -0,0 +1,143 @@
+//! Проверка команд `device-config`: коды завершения, вывод JSON и сохранение значений
+
+use std::process::{Command, Output};
+
+use serde_json::Value;
+
+/// Копия файла-схемы из примеров config_lib: каждый тест работает со своей копией
+fn copy_example_scheme(name: &str) -> String {
+    let source = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../config_lib/examples/simple_config.toml"
+    );
+    let path = std::env::temp_dir().join(format!(
+        "device_config_cli_{}_{}.toml",
+        name,
+        std::process::id()
+    ));
+    std::fs::copy(source, &path).unwrap();
+    path.to_str().unwrap().to_string()
+}
+
+/// Запуск `device-config` со схемой `scheme` и аргументами `args`
+fn run(scheme: &str, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_device-config"))
+        .args(["--scheme", scheme])
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+/// Разбор JSON, выведенного командой
+fn stdout_json(output: &Output) -> Value {
+    serde_json::from_slice(&output.stdout).unwrap()
+}
+
+#[test]
+fn test_set_and_reset_persist() {
+    let scheme = copy_example_scheme("persist");
+
+    // Значение вводится без единицы измерения, сохраняется допустимое значение
+    let output = run(&scheme, &["--json", "set", "soundvolume", "25"]);
+    assert_eq!(output.status.code(), Some(0));
+    let change = stdout_json(&output);
+    assert_eq!(change["old"], "100%");
+    assert_eq!(change["new"], "25%");
+
+    // Значение сохранено в файл-схему
+    let output = run(&scheme, &["get", "soundvolume"]);
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "25%");
+
+    // Доступ по прежнему имени параметра
+    let output = run(&scheme, &["--json", "set", "musicvolume", "max"]);
+    assert_eq!(output.status.code(), Some(0));
+    let output = run(&scheme, &["--json", "get", "bgm_volume"]);
+    assert_eq!(stdout_json(&output)["value"], "100%");
+
+    let output = run(&scheme, &["reset", "soundvolume"]);
+    assert_eq!(output.status.code(), Some(0));
+    let output = run(&scheme, &["--json", "list"]);
+    let parameters = stdout_json(&output);
+    let soundvolume = parameters
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|parameter| parameter["key"] == "soundvolume")
+        .unwrap();
+    assert_eq!(soundvolume["value"], "50%");
+
+    std::fs::remove_file(&scheme).unwrap();
+}
+
+#[test]
+fn test_exit_codes() {
+    let scheme = copy_example_scheme("exit_codes");
+    let original = std::fs::read_to_string(&scheme).unwrap();
+
+    // Неизвестный параметр
+    let output = run(&scheme, &["get", "unknown"]);
+    assert_eq!(output.status.code(), Some(3));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("unknown"));
+
+    // Значение вне списка допустимых
+    let output = run(&scheme, &["set", "soundvolume", "33%"]);
+    assert_eq!(output.status.code(), Some(4));
+
+    // Нарушение ограничения схемы: файл-схема не изменяется
+    let output = run(&scheme, &["set", "groupnumber", "0"]);
+    assert_eq!(output.status.code(), Some(0));
+    let saved = std::fs::read_to_string(&scheme).unwrap();
+    let output = run(&scheme, &["set", "loadcapacity", "1600кг 21чел."]);
+    assert_eq!(output.status.code(), Some(5));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("loadcapacity = 1600кг 21чел."));
+    assert_eq!(std::fs::read_to_string(&scheme).unwrap(), saved);
+
+    // Некорректное значение в файле-схеме
+    std::fs::write(
+        &scheme,
+        original.replace("current = \"100%\"", "current = \"33%\""),
+    )
+    .unwrap();
+    let output = run(&scheme, &["validate"]);
+    assert_eq!(output.status.code(), Some(5));
+    assert!(
+        String::from_utf8_lossy(&output.stderr)
+            .contains("Parameter soundvolume has invalid value 33%")
+    );
+
+    std::fs::write(&scheme, original).unwrap();
+    let output = run(&scheme, &["--json", "validate"]);
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(stdout_json(&output)["valid"], true);
+
+    std::fs::remove_file(&scheme).unwrap();
+}
+
+#[test]
+fn test_json_errors() {
+    let scheme = copy_example_scheme("json_errors");
+
+    for (args, code) in [
+        (vec!["--json", "options", "unknown"], 3),
+        (vec!["--json", "set", "soundvolume", "33%"], 4),
+        (vec!["--json", "reset", "unknown"], 3),
+    ] {
+        let output = run(&scheme, &args);
+        assert_eq!(output.status.code(), Some(code));
+
+        let error = stdout_json(&output);
+        assert_eq!(error["code"], code);
+        assert!(
+            error["error"]
+                .as_str()
+                .is_some_and(|error| !error.is_empty())
+        );
+    }
+
+    // Ошибка чтения файла-схемы
+    let output = run("missing_scheme.toml", &["--json", "list"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(stdout_json(&output)["code"], 1);
+
+    std::fs::remove_file(&scheme).unwrap();
+}
@@ -25,15 +25,14 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Запускаем главный цикл обработки событий
     menu.run();
 
-    let device_config = menu.get_schema_config()?;
+    let device_config = menu.get_shared_config().snapshot();
 
     println!("Итоговая конфигурация:");
-    for parameter in device_config.get_parameters_names()? {
-        let value = device_config.get_parameter_value(&parameter).unwrap();
+    for (parameter, value) in device_config.get_parameters_values() {
         println!("{}: {}", parameter, value);
     }
 
-    return Ok(());
+    Ok(())
 }
 
 // Симуляция обработчиков GPIO кнопок
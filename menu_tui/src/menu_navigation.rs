@@ -1,7 +1,7 @@
 //! # Модуль навигации по меню
 //!
 //! > Позволяет имитировать нажатия определенных клавиш в процессе работы с меню.
-//! Используется если в системе отсутствует клавиатура.
+//! > Используется если в системе отсутствует клавиатура.
 //!
 //!
 
@@ -13,6 +13,7 @@ use cursive::views::{CircularFocus, ListView, SelectView};
 use cursive::{CbSink, Cursive, View};
 
 // TODO: Перенести в отдельный модуль
+#[allow(dead_code, clippy::enum_variant_names)]
 enum ActiveMenuView {
     MainView,
     ConfigView,
@@ -39,6 +40,8 @@ pub struct NavigationManager {
     cb_sink: CbSink,
     current_view: Arc<Mutex<String>>,
     last_activity: Arc<Mutex<Instant>>,
+    /// Таймер бездействия приостановлен (например, показано сообщение об ошибке сохранения)
+    idle_paused: Arc<Mutex<bool>>,
 }
 
 impl NavigationManager {
@@ -48,12 +51,13 @@ impl NavigationManager {
             cb_sink,
             current_view: Arc::new(Mutex::new("main_menu".to_string())),
             last_activity: Arc::new(Mutex::new(Instant::now())),
+            idle_paused: Arc::new(Mutex::new(false)),
         }
     }
 
     /// Получить время последнего нажатия клавиши
     pub fn get_last_activity(&self) -> Instant {
-        self.last_activity.lock().unwrap().clone()
+        *self.last_activity.lock().unwrap()
     }
 
     /// Установить время последнего нажатия клавиши
@@ -62,6 +66,16 @@ impl NavigationManager {
         *last_time = instant;
     }
 
+    /// Приостановлен ли таймер бездействия
+    pub fn is_idle_paused(&self) -> bool {
+        *self.idle_paused.lock().unwrap()
+    }
+
+    /// Приостановить (возобновить) таймер бездействия
+    pub(crate) fn set_idle_paused(&self, paused: bool) {
+        *self.idle_paused.lock().unwrap() = paused;
+    }
+
     /// Установить текущий активный View (Cursive)
     pub(crate) fn set_current_view(&self, view_name: &str) {
         let mut current = self.current_view.lock().unwrap();
@@ -135,20 +149,18 @@ impl NavigationManager {
             .call_on_name(view_name, |view: &mut CircularFocus<SelectView<i32>>| {
                 view.on_event(event.clone())
             })
+            && let EventResult::Consumed(Some(callback)) = result
         {
-            if let EventResult::Consumed(Some(callback)) = result {
-                callback(siv);
-                return;
-            }
+            callback(siv);
+            return;
         }
 
         // Для CircularFocus<ListView> - меню параметров устройства
         if let Some(result) = siv.call_on_name(view_name, |view: &mut CircularFocus<ListView>| {
             view.on_event(event.clone())
-        }) {
-            if let EventResult::Consumed(Some(callback)) = result {
-                callback(siv);
-            }
+        }) && let EventResult::Consumed(Some(callback)) = result
+        {
+            callback(siv);
         }
     }
 }
@@ -160,6 +172,7 @@ impl Clone for NavigationManager {
             cb_sink: self.cb_sink.clone(),
             current_view: Arc::clone(&self.current_view),
             last_activity: Arc::clone(&self.last_activity),
+            idle_paused: Arc::clone(&self.idle_paused),
         }
     }
 }
@@ -2,47 +2,65 @@
 //!
 //! ## Навигация
 //! > Для навигации по меню используется клавиши стрелка вверх/вниз
-//! Выбор элемента в меню происходит клавишей Enter
-//! `В случае отсутствия данных клавиш в системе, можно эмулировать их с помощью модуля menu_navigation
+//! > Выбор элемента в меню происходит клавишей Enter
+//! > `В случае отсутствия данных клавиш в системе, можно эмулировать их с помощью модуля menu_navigation
 //!
 //! ## Параметры меню
 //! > Параметры меню подтягиваются из файла-схемы TOML с помощью крейта config_lib
 
+use std::sync::mpsc::Receiver;
 use std::time::{Duration, Instant};
 
+use config_lib::change_events::ChangeEvent;
 use config_lib::device_config::DeviceConfig;
+use config_lib::shared_config::SharedConfig;
 use cursive::{
     Cursive, CursiveExt,
     event::{Event, EventResult, EventTrigger},
 };
 
 use crate::{
-    menu_navigation::NavigationManager, user_parameters::DeviceParameters,
-    views::main_view::show_main_view,
+    menu_navigation::NavigationManager,
+    user_parameters::DeviceParameters,
+    views::{exit_view::show_exit_view, main_view::show_main_view},
 };
 
 pub struct DeviceMenu {
     siv: Cursive,
     nav_manager: NavigationManager,
-    scheme_config: DeviceConfig,
+    shared_config: SharedConfig,
 }
 
 pub(crate) struct MenuAppState {
     pub navigation_manager: NavigationManager,
     pub inner_config: DeviceParameters,
+    /// Конфигурация устройства, все изменения значений выполняются через нее
+    pub scheme_config: SharedConfig,
+    /// События изменения значений `scheme_config`
+    config_changes: Receiver<ChangeEvent>,
+    /// Выход выполнен без сохранения изменений
+    pub discard_changes: bool,
+}
+
+impl MenuAppState {
+    /// Перенос изменений `scheme_config` в отображаемые пользовательские параметры
+    pub fn apply_config_changes(&mut self) {
+        for event in self.config_changes.try_iter() {
+            self.inner_config
+                .update_parameter(&event.key, event.new_value);
+        }
+    }
 }
 
 impl DeviceMenu {
     pub fn new(path_to_scheme: &str, theme_path: &str) -> Self {
         // Получение конфигурации устройства
-        let device_config = DeviceConfig::create_parameter_list(path_to_scheme)
+        let mut device_config = DeviceConfig::create_parameter_list(path_to_scheme)
             .expect("Ошибка загрузки config_scheme");
 
         let mut siv = Cursive::default();
 
-        siv.set_global_callback('q', |siv: &mut Cursive| {
-            siv.quit();
-        });
+        siv.set_global_callback('q', show_exit_view);
         // TODO: обработка ошибок
         let mut device_parameters: DeviceParameters = DeviceParameters::new();
 
@@ -54,14 +72,21 @@ impl DeviceMenu {
         let nav_manager = NavigationManager::new(siv.cb_sink().clone());
 
         // TODO: Cursive logs example
-        if let Err(_) = siv.load_theme_file(theme_path) {
-            println!("{}", format!("Не удалось загрузить тему: {theme_path}"));
+        if siv.load_theme_file(theme_path).is_err() {
+            println!("Не удалось загрузить тему: {theme_path}");
         }
 
+        let config_changes = device_config.subscribe();
+        let shared_config =
+            SharedConfig::new(device_config).expect("Ошибка загрузки config_scheme");
+
         // Сохраняем и NavigationManager и конфигурацию
         let app_state = MenuAppState {
             navigation_manager: nav_manager.clone(),
             inner_config: device_parameters,
+            scheme_config: shared_config.clone(),
+            config_changes,
+            discard_changes: false,
         };
 
         // При работе в меню с исподьзованием обычной клавиатуры, обновляем last_activity (активность пользователя)
@@ -78,7 +103,7 @@ impl DeviceMenu {
         DeviceMenu {
             siv,
             nav_manager,
-            scheme_config: device_config,
+            shared_config,
         }
     }
 
@@ -101,6 +126,8 @@ impl DeviceMenu {
     /// ## Запуск таймера, который проверяет активность пользователя
     /// > Если пользователь не активен в течение `timeout_seconds`, то приложение закрывается.
     /// > Каждый раз, когда пользователь нажимает одну из кнопок навигации, сбрасывается таймер.
+    /// > Если значения параметров нарушают ограничения схемы, приложение не закрывается:
+    /// > показывается сообщение об ошибке, пока оно открыто таймер приостановлен.
     pub fn launch_idling_watchdog(&mut self, timeout_seconds: u64) {
         let nav_manager = self.get_navigation_manager();
         let cb_sink = self.siv.cb_sink().clone();
@@ -109,6 +136,11 @@ impl DeviceMenu {
             loop {
                 std::thread::sleep(Duration::from_secs(1));
 
+                // Таймер приостановлен, пока показано сообщение об ошибке сохранения
+                if nav_manager.is_idle_paused() {
+                    continue;
+                }
+
                 // Проверяем активность
                 let is_active = {
                     let last = nav_manager.get_last_activity();
@@ -116,31 +148,29 @@ impl DeviceMenu {
                 };
 
                 if !is_active {
-                    cb_sink
-                        .send(Box::new(|s: &mut Cursive| {
-                            s.quit();
-                        }))
-                        .ok();
-                    break;
+                    if cb_sink.send(Box::new(show_exit_view)).is_err() {
+                        break;
+                    }
+                    nav_manager.set_last_activity(Instant::now());
                 }
             }
         });
     }
 
+    /// Выход выполнен без сохранения изменений (выбран пользователем при ошибке сохранения)
+    pub fn is_changes_discarded(&mut self) -> bool {
+        self.siv
+            .user_data::<MenuAppState>()
+            .is_some_and(|app_state| app_state.discard_changes)
+    }
+
     /// Закрытие приложения
     pub fn quit(&mut self) {
         self.siv.quit();
     }
 
-    /// Получить текущую конфигурацию
-    pub fn get_schema_config(&mut self) -> Result<DeviceConfig, String> {
-        // TODO: обработка ошибок
-        if let Some(app_state) = self.siv.take_user_data::<MenuAppState>() {
-            app_state
-                .inner_config
-                .update_user_config(&mut self.scheme_config)?;
-        }
-
-        Ok(self.scheme_config.clone())
+    /// Получить дескриптор текущей конфигурации (конфигурация не копируется)
+    pub fn get_shared_config(&self) -> SharedConfig {
+        self.shared_config.clone()
     }
 }
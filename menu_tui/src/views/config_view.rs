@@ -1,11 +1,22 @@
+use std::collections::HashMap;
+
 use crate::menu_process::MenuAppState;
+use crate::user_parameters::get_hint;
 use cursive::{
     Cursive,
     align::HAlign,
-    view::{Nameable, Resizable},
-    views::{Button, CircularFocus, Dialog, ListView, SelectView},
+    view::{Nameable, Resizable, ViewWrapper},
+    views::{
+        Button, CircularFocus, Dialog, LinearLayout, ListChild, ListView, NamedView, SelectView,
+        TextView,
+    },
 };
 
+/// Имя строки подсказки под списком параметров
+const HINT_VIEW_NAME: &str = "params_hint";
+/// Имя списка параметров
+const PARAMS_VIEW_NAME: &str = "params_menu";
+
 /// Отображение диалога выбора значения параметров устройства
 pub(crate) fn show_config_view(siv: &mut Cursive) {
     // Получение текущего состояния приложения
@@ -14,22 +25,27 @@ pub(crate) fn show_config_view(siv: &mut Cursive) {
         .expect("Не удалось выполнить take_user_data");
 
     // Обновляем current_view
-    app_state.navigation_manager.set_current_view("params_menu");
+    app_state
+        .navigation_manager
+        .set_current_view(PARAMS_VIEW_NAME);
 
     let mut parameter_list = ListView::new();
 
     // Разделитель
     parameter_list.add_delimiter();
 
+    // Подсказки к параметрам (ключ - ключ параметра, подписи строк могут совпадать)
+    let mut hints: HashMap<String, String> = HashMap::new();
+
     // Создание списка параметров c выпадающими списками возможных значений
     for parameter in app_state.inner_config.parameters.iter() {
         let key = parameter.key.clone();
-        let current_value = parameter.selected_value.clone();
+        let current_value = parameter.value.clone();
 
         let mut select_view = SelectView::new().popup().h_align(HAlign::Left);
 
         let mut selected_index = 0;
-        for (index, option) in parameter.options.iter().enumerate() {
+        for (index, option) in parameter.possible_values.iter().enumerate() {
             select_view.add_item(option, option.clone());
             if option == &current_value {
                 selected_index = index;
@@ -46,17 +62,37 @@ pub(crate) fn show_config_view(siv: &mut Cursive) {
                 .take_user_data()
                 .expect("Не удалось выполнить take_user_data");
 
-            state
-                .inner_config
-                .update_parameter(&key_for_callback, selected_value.clone());
+            if let Err(error) = state.scheme_config.update(|config| {
+                config.set_parameter_value(&key_for_callback, selected_value.clone())
+            }) {
+                s.add_layer(Dialog::info(error).title("⚠ Ошибка"));
+            }
+            state.apply_config_changes();
             s.set_user_data(state);
         });
 
         let parameter_view = select_view.with_name(key);
 
         parameter_list.add_child(&parameter.description, parameter_view);
+        hints.insert(parameter.key.clone(), get_hint(parameter));
     }
 
+    // Подсказка для первого параметра, на котором изначально стоит фокус
+    let initial_hint = app_state
+        .inner_config
+        .parameters
+        .first()
+        .map(get_hint)
+        .unwrap_or_default();
+
+    // Обновление подсказки при перемещении фокуса по списку
+    parameter_list.set_on_select(move |s, _label: &String| {
+        let hint = focused_parameter_key(s)
+            .and_then(|key| hints.get(&key).cloned())
+            .unwrap_or_default();
+        s.call_on_name(HINT_VIEW_NAME, |view: &mut TextView| view.set_content(hint));
+    });
+
     // Разделитель
     parameter_list.add_delimiter();
 
@@ -76,9 +112,13 @@ pub(crate) fn show_config_view(siv: &mut Cursive) {
     // Обёртка для циклической навигации с помощью одной кнопки
     let circular_list = CircularFocus::new(parameter_list)
         .with_wrap_arrows(true)
-        .with_name("params_menu");
+        .with_name(PARAMS_VIEW_NAME);
+
+    let layout = LinearLayout::vertical()
+        .child(circular_list)
+        .child(TextView::new(initial_hint).with_name(HINT_VIEW_NAME));
 
-    let parameter_dialog = Dialog::around(circular_list)
+    let parameter_dialog = Dialog::around(layout)
         .title("⚙ Параметры устройства")
         .fixed_width(50);
 
@@ -87,3 +127,17 @@ pub(crate) fn show_config_view(siv: &mut Cursive) {
 
     siv.add_layer(parameter_dialog);
 }
+
+/// Ключ параметра в строке списка, на которой стоит фокус (имя выпадающего списка значений)
+fn focused_parameter_key(siv: &mut Cursive) -> Option<String> {
+    siv.call_on_name(PARAMS_VIEW_NAME, |view: &mut CircularFocus<ListView>| {
+        view.with_view(|list| match list.get_row(list.focus()) {
+            ListChild::Row(_, child) => child
+                .downcast_ref::<NamedView<SelectView<String>>>()
+                .map(|select_view| select_view.name().to_string()),
+            ListChild::Delimiter => None,
+        })
+    })
+    .flatten()
+    .flatten()
+}
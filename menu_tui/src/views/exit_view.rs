@@ -1,6 +1,79 @@
-use cursive::Cursive;
+use std::time::Instant;
+
+use crate::menu_process::MenuAppState;
+use cursive::{
+    Cursive,
+    view::Nameable,
+    views::{CircularFocus, Dialog, LinearLayout, SelectView, TextView},
+};
+
+/// Имя окна с сообщением о невозможности сохранения
+const SAVE_ERROR_VIEW: &str = "save_error";
 
 /// Закрытие приложения с сохранением параметров
+/// Вызывается при любом способе выхода: пункт меню, клавиша `q`, таймер бездействия.
+/// Если сохраняемые значения параметров нарушают ограничения схемы, выход не выполняется:
+/// показывается сообщение с выбором - вернуться в меню или выйти без сохранения изменений
 pub(crate) fn show_exit_view(siv: &mut Cursive) {
+    let app_state: MenuAppState = siv
+        .take_user_data()
+        .expect("Не удалось выполнить take_user_data");
+
+    let validation = app_state
+        .scheme_config
+        .read(|config| config.validate_scheme_values());
+    let navigation_manager = app_state.navigation_manager.clone();
+
+    siv.set_user_data(app_state);
+
+    match validation {
+        Ok(()) => siv.quit(),
+        // Сообщение уже показано
+        Err(_) if siv.find_name::<Dialog>(SAVE_ERROR_VIEW).is_some() => {}
+        Err(error) => {
+            // Пока сообщение открыто, таймер бездействия не закрывает приложение
+            navigation_manager.set_idle_paused(true);
+
+            // Выбор списком: навигация кнопками GPIO - только вверх/вниз и выбор
+            let choice = SelectView::new()
+                .item("Вернуться в меню", false)
+                .item("Выйти без сохранения", true)
+                .on_submit(|s: &mut Cursive, discard: &bool| {
+                    if *discard {
+                        discard_changes_and_exit(s);
+                    } else {
+                        close_save_error_view(s);
+                    }
+                });
+
+            siv.add_layer(
+                Dialog::around(
+                    LinearLayout::vertical()
+                        .child(TextView::new(format!("Сохранение невозможно:\n{error}\n")))
+                        .child(CircularFocus::new(choice).with_wrap_arrows(true)),
+                )
+                .title("⚠ Ошибка")
+                .with_name(SAVE_ERROR_VIEW),
+            );
+        }
+    }
+}
+
+/// Закрытие сообщения о невозможности сохранения, таймер бездействия запускается заново
+fn close_save_error_view(siv: &mut Cursive) {
+    siv.pop_layer();
+    if let Some(app_state) = siv.user_data::<MenuAppState>() {
+        app_state
+            .navigation_manager
+            .set_last_activity(Instant::now());
+        app_state.navigation_manager.set_idle_paused(false);
+    }
+}
+
+/// Закрытие приложения без сохранения измененных значений параметров
+fn discard_changes_and_exit(siv: &mut Cursive) {
+    if let Some(app_state) = siv.user_data::<MenuAppState>() {
+        app_state.discard_changes = true;
+    }
     siv.quit();
 }
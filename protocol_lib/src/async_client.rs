@@ -0,0 +1,346 @@
+//! # Асинхронный клиент интерфейсной платы (tokio)
+//!
+//! > Транспорт обслуживается фоновой задачей: она отправляет запросы по очереди, направляет отклики
+//! > ожидающим запросам, а фреймы, принятые без запроса, - в поток `unsolicited_frames`.
+//! > Отмена запроса (drop future) безопасна: следующий запрос отправляется только после получения
+//! > отклика на предыдущий или истечения его срока, поэтому отклики не достаются чужим запросам.
+
+use std::time::Duration;
+
+use log::{debug, error, trace, warn};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::sync::{broadcast, mpsc, oneshot};
+use tokio::time::Instant;
+use tokio_stream::{Stream, StreamExt, wrappers::BroadcastStream};
+
+use crate::client::HANDSHAKE_ATTEMPTS;
+use crate::dispatcher::ReplyFilter;
+use crate::elevator_state::ElevatorState;
+use crate::frame_decoder::MUFrameDecoder;
+use crate::mu_frame::MUFrame;
+use crate::opcode::Opcode;
+
+/// Количество запросов, ожидающих отправки
+const REQUEST_QUEUE_SIZE: usize = 16;
+/// Количество фреймов, принятых без запроса, хранимых для медленных подписчиков
+const UNSOLICITED_QUEUE_SIZE: usize = 64;
+
+/// Запрос к фоновой задаче
+struct Request {
+    frame: MUFrame,
+    filter: ReplyFilter,
+    reply: oneshot::Sender<Result<MUFrame, String>>,
+}
+
+/// Запрос, ожидающий отклика
+struct PendingRequest {
+    filter: ReplyFilter,
+    reply: oneshot::Sender<Result<MUFrame, String>>,
+    deadline: Instant,
+}
+
+/// Асинхронный клиент интерфейсной платы
+/// Клонирование дескриптора не создает нового соединения: запросы всех копий выполняются по очереди
+#[derive(Debug, Clone)]
+pub struct AsyncHostClient {
+    requests: mpsc::Sender<Request>,
+    unsolicited: broadcast::Sender<MUFrame>,
+}
+
+impl AsyncHostClient {
+    /// Создание соединения через TCP (`host:port`) с проверкой связи (handshake)
+    /// * `timeout` - максимальное время ожидания отклика на запрос
+    pub async fn connect_tcp(address: &str, timeout: Duration) -> Result<Self, String> {
+        let stream = tokio::net::TcpStream::connect(address)
+            .await
+            .map_err(|e| format!("Unable to connect to {address}: {e}"))?;
+        stream.set_nodelay(true).map_err(|e| e.to_string())?;
+
+        Self::new(stream, timeout).await
+    }
+
+    /// Создание соединения через транспорт `transport` с проверкой связи (handshake)
+    /// Должно вызываться в контексте tokio runtime: транспорт обслуживается фоновой задачей
+    /// * `timeout` - максимальное время ожидания отклика на запрос
+    pub async fn new<T>(transport: T, timeout: Duration) -> Result<Self, String>
+    where
+        T: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    {
+        let (requests, receiver) = mpsc::channel(REQUEST_QUEUE_SIZE);
+        let (unsolicited, _) = broadcast::channel(UNSOLICITED_QUEUE_SIZE);
+        tokio::spawn(serve_transport(
+            transport,
+            receiver,
+            unsolicited.clone(),
+            timeout,
+        ));
+
+        let client = AsyncHostClient {
+            requests,
+            unsolicited,
+        };
+        client.try_handshake().await?;
+        Ok(client)
+    }
+
+    /// Попытка установить соединение с устройством
+    async fn try_handshake(&self) -> Result<(), String> {
+        for attempt in 1..=HANDSHAKE_ATTEMPTS {
+            warn!("Handshake attempt: {attempt}");
+
+            match self.send_request("hello").await {
+                Ok(response) if response.as_bytes() == b"Hi!\r\n" => return Ok(()),
+                Ok(response) => warn!("Response from device: {response}"),
+                Err(e) => error!("Handshake: {e}"),
+            }
+        }
+        Err("Handshake failed!".to_string())
+    }
+
+    /// Отправка запроса на устройство, возврат отклика на него (см. `ReplyFilter::console_reply`)
+    pub async fn send_request(&self, request: &str) -> Result<String, String> {
+        self.send_request_matching(request, ReplyFilter::console_reply(request))
+            .await
+    }
+
+    /// Отправка запроса на устройство, возврат отклика, соответствующего `filter`
+    pub async fn send_request_matching(
+        &self,
+        request: &str,
+        filter: ReplyFilter,
+    ) -> Result<String, String> {
+        let frame = MUFrame::console(&format!("{}{}", request, "\n"))?;
+        let reply = self.exchange_matching(frame, filter).await?;
+
+        String::from_utf8(reply.get_data().to_vec()).map_err(|e| e.to_string())
+    }
+
+    /// Отправка команды на устройство с ожиданием подтверждения приема
+    pub async fn send_command(&self, command: &str) -> Result<(), String> {
+        self.exchange(MUFrame::command(command)?, Opcode::Ack)
+            .await?;
+        Ok(())
+    }
+
+    /// Запрос состояния лифта
+    pub async fn request_status(&self) -> Result<ElevatorState, String> {
+        let reply = self
+            .exchange(MUFrame::status_request(), Opcode::ElevatorState)
+            .await?;
+
+        let state = String::from_utf8(reply.get_data().to_vec()).map_err(|e| e.to_string())?;
+        ElevatorState::parse_lenient(&state).map_err(|e| e.to_string())
+    }
+
+    /// Отправка произвольного фрейма, прием отклика с кодом операции `expected`
+    pub async fn exchange(&self, frame: MUFrame, expected: Opcode) -> Result<MUFrame, String> {
+        self.exchange_matching(frame, ReplyFilter::opcode(expected))
+            .await
+    }
+
+    /// Отправка произвольного фрейма, прием отклика, соответствующего `filter`
+    pub async fn exchange_matching(
+        &self,
+        frame: MUFrame,
+        filter: ReplyFilter,
+    ) -> Result<MUFrame, String> {
+        let (reply, response) = oneshot::channel();
+        self.requests
+            .send(Request {
+                frame,
+                filter,
+                reply,
+            })
+            .await
+            .map_err(|_| "Connection closed".to_string())?;
+
+        response
+            .await
+            .map_err(|_| "Connection closed".to_string())?
+    }
+
+    /// Поток фреймов, принятых без запроса (телеметрия и т.п.), начиная с момента вызова
+    /// Фреймы, не прочитанные вовремя медленным подписчиком, пропускаются
+    pub fn unsolicited_frames(&self) -> impl Stream<Item = MUFrame> + Send + Unpin + 'static {
+        BroadcastStream::new(self.unsolicited.subscribe()).filter_map(|frame| match frame {
+            Ok(frame) => Some(frame),
+            Err(e) => {
+                warn!("Unsolicited frames: {e}");
+                None
+            }
+        })
+    }
+}
+
+/// Обслуживание транспорта: отправка запросов по очереди, прием и распределение фреймов
+/// Задача завершается при закрытии соединения или удалении всех копий клиента
+async fn serve_transport<T>(
+    transport: T,
+    mut requests: mpsc::Receiver<Request>,
+    unsolicited: broadcast::Sender<MUFrame>,
+    timeout: Duration,
+) where
+    T: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    let (mut reader, mut writer) = tokio::io::split(transport);
+    let mut decoder = MUFrameDecoder::new();
+    let mut read_buffer = [0; 256];
+    let mut pending: Option<PendingRequest> = None;
+
+    loop {
+        while let Some(frame) = decoder.next_frame() {
+            let opcode = frame.get_opcode();
+            match pending.take() {
+                Some(request) if request.filter.matches(&frame) => {
+                    // Запрос мог быть отменен: отклик в этом случае отбрасывается
+                    let _ = request.reply.send(Ok(frame));
+                }
+                other => {
+                    pending = other;
+                    // Консольные фреймы - отклики: не соответствующие запросу (запоздавшие) отбрасываются
+                    if opcode == Opcode::Console {
+                        debug!(
+                            "Stale console reply dropped: {:?}",
+                            String::from_utf8_lossy(frame.get_data())
+                        );
+                        continue;
+                    }
+                    trace!("Unsolicited frame: {opcode}");
+                    if let Opcode::Unknown(_) = opcode {
+                        warn!("Received frame with unknown opcode: {opcode}");
+                    }
+                    // Ошибка - нет подписчиков
+                    let _ = unsolicited.send(frame);
+                }
+            }
+        }
+
+        let deadline = pending.as_ref().map(|request| request.deadline);
+        tokio::select! {
+            received = reader.read(&mut read_buffer) => match received {
+                Ok(0) | Err(_) => {
+                    if let Some(request) = pending.take() {
+                        let _ = request.reply.send(Err("Connection closed".to_string()));
+                    }
+                    return;
+                }
+                Ok(received) => {
+                    trace!("Received raw message: {:?}", &read_buffer[..received]);
+                    decoder.push(&read_buffer[..received]);
+                }
+            },
+            request = requests.recv(), if pending.is_none() => {
+                let Some(request) = request else {
+                    return;
+                };
+                // Запрос отменен до отправки
+                if request.reply.is_closed() {
+                    continue;
+                }
+                let bytes = request.frame.serialize();
+                let written = match writer.write_all(&bytes).await {
+                    Ok(()) => writer.flush().await,
+                    Err(e) => Err(e),
+                };
+                match written {
+                    Ok(()) => {
+                        pending = Some(PendingRequest {
+                            filter: request.filter,
+                            reply: request.reply,
+                            deadline: Instant::now() + timeout,
+                        });
+                    }
+                    Err(e) => {
+                        let _ = request.reply.send(Err(e.to_string()));
+                    }
+                }
+            },
+            _ = tokio::time::sleep_until(deadline.unwrap_or_else(Instant::now)), if deadline.is_some() => {
+                if let Some(request) = pending.take() {
+                    let _ = request.reply.send(Err(crate::RESPONSE_TIMEOUT.to_string()));
+                }
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::DuplexStream;
+
+    /// Эмулятор интерфейсной платы: на запрос `get state` сначала передает телеметрию,
+    /// на `get slow` отвечает с задержкой, на `ignore` не отвечает, на `get <name>` - `<name>:0`
+    async fn run_board(mut board: DuplexStream) {
+        let mut decoder = MUFrameDecoder::new();
+        let mut buffer = [0; 256];
+        let telemetry = MUFrame::elevator_state("#STM:L3:R0:A1:S0:M0:E#\r\n").unwrap();
+        loop {
+            while let Some(frame) = decoder.next_frame() {
+                let reply = match frame.get_data() {
+                    b"hello\n" => "Hi!\r\n".to_string(),
+                    b"ignore\n" => continue,
+                    b"get state\n" => {
+                        board.write_all(&telemetry.serialize()).await.unwrap();
+                        "state:ok".to_string()
+                    }
+                    b"get slow\n" => {
+                        tokio::time::sleep(Duration::from_millis(50)).await;
+                        "slow:ok".to_string()
+                    }
+                    request => {
+                        let request = String::from_utf8_lossy(request);
+                        format!("{}:0", request.trim().trim_start_matches("get "))
+                    }
+                };
+                let reply = MUFrame::console(&reply).unwrap();
+                board.write_all(&reply.serialize()).await.unwrap();
+            }
+            match board.read(&mut buffer).await {
+                Ok(0) | Err(_) => return,
+                Ok(received) => decoder.push(&buffer[..received]),
+            }
+        }
+    }
+
+    async fn create_client() -> AsyncHostClient {
+        let (host, board) = tokio::io::duplex(1024);
+        tokio::spawn(run_board(board));
+        AsyncHostClient::new(host, Duration::from_millis(200))
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_requests_and_unsolicited_frames() {
+        let client = create_client().await;
+        let mut frames = client.unsolicited_frames();
+
+        assert_eq!(client.send_request("get state").await.unwrap(), "state:ok");
+        let frame = frames.next().await.unwrap();
+        assert_eq!(frame.get_opcode(), Opcode::ElevatorState);
+
+        assert_eq!(
+            client.send_request("ignore").await,
+            Err("Response timeout".to_string())
+        );
+        assert_eq!(
+            client.send_request("get soundvolume").await.unwrap(),
+            "soundvolume:0"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_request() {
+        let client = create_client().await;
+
+        // Отклик на отмененный запрос не достается следующему запросу
+        let cancelled =
+            tokio::time::timeout(Duration::from_millis(10), client.send_request("get slow")).await;
+        assert!(cancelled.is_err());
+        assert_eq!(
+            client.send_request("get soundvolume").await.unwrap(),
+            "soundvolume:0"
+        );
+    }
+}
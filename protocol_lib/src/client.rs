@@ -1,42 +1,79 @@
-use log::{debug, error, warn};
+use log::{error, warn};
 
+use crate::dispatcher::{Dispatcher, ReplyFilter};
+use crate::elevator_state::ElevatorState;
+use crate::frame_decoder::MUFrameDecoder;
 use crate::mu_frame::MUFrame;
-use std::time::Duration;
+use crate::opcode::Opcode;
+use crate::transport::{SerialTransport, Transport, open_transport};
+use std::time::{Duration, Instant};
 
 /// Количество попыток установить соединение c MCU
-const HANDSHAKE_ATTEMPTS: u8 = 5;
-
-pub struct HostClient {
-    serial_port: Box<dyn serialport::SerialPort + 'static>,
+pub(crate) const HANDSHAKE_ATTEMPTS: u8 = 5;
+
+/// Максимальное количество хранимых фреймов, принятых без запроса
+const MAX_UNSOLICITED_FRAMES: usize = 64;
+
+/// Клиент интерфейсной платы поверх транспорта `T`
+/// По умолчанию транспорт выбирается во время выполнения (`connect`, `connect_url`)
+pub struct HostClient<T: Transport = Box<dyn Transport>> {
+    transport: T,
+    /// Декодер принятых фреймов, хранит байты, принятые после последнего фрейма
+    decoder: MUFrameDecoder,
+    /// Распределитель принятых фреймов: отклики и фреймы, принятые без запроса (телеметрия и т.п.)
+    dispatcher: Dispatcher,
+    /// Максимальное время ожидания отклика на запрос
+    request_timeout: Duration,
 }
 
 impl HostClient {
     /// Создание соединения host - интерфейсная плата over UART
+    /// * `timeout` - максимальное время ожидания отклика на запрос
     pub fn connect(
         port_name: &str,
         baudrate: u32,
         timeout: Duration,
     ) -> Result<HostClient, String> {
-        let serial_port = serialport::new(port_name, baudrate)
-            .timeout(timeout)
-            .open()
-            .unwrap_or_else(|_| panic!("Unable to open serial port: {port_name}"));
+        let transport = SerialTransport::open(port_name, baudrate)?;
+
+        HostClient::new(Box::new(transport), timeout)
+    }
+
+    /// Создание соединения с интерфейсной платой через транспорт, заданный `url`
+    /// (`serial:///dev/ttyS4?baud=115200`, `tcp://host:port`, `pty:///dev/pts/3`)
+    /// * `timeout` - максимальное время ожидания отклика на запрос
+    pub fn connect_url(url: &str, timeout: Duration) -> Result<HostClient, String> {
+        HostClient::new(open_transport(url)?, timeout)
+    }
+}
+
+impl<T: Transport> HostClient<T> {
+    /// Создание соединения через транспорт `transport` с проверкой связи (handshake)
+    /// * `timeout` - максимальное время ожидания отклика на запрос
+    pub fn new(mut transport: T, timeout: Duration) -> Result<Self, String> {
+        transport
+            .set_read_timeout(crate::READ_POLL_INTERVAL.min(timeout))
+            .map_err(|e| e.to_string())?;
 
-        Self::try_handshake(serial_port)
+        Self::try_handshake(transport, timeout)
     }
 
     /// Попытка установить соединение с устройством
-    fn try_handshake(instance: Box<dyn serialport::SerialPort + 'static>) -> Result<Self, String> {
+    fn try_handshake(transport: T, request_timeout: Duration) -> Result<Self, String> {
         let mut attempts: u8 = 1;
 
         let mut client_connection = HostClient {
-            serial_port: instance,
+            transport,
+            decoder: MUFrameDecoder::new(),
+            dispatcher: Dispatcher::new(MAX_UNSOLICITED_FRAMES),
+            request_timeout,
         };
 
         // Цикл попыток установить соединение
         'handshake_loop: loop {
             warn!("Handshake attempt: {attempts}");
 
+            // Запоздавшие отклики на запросы предыдущего сеанса не принимаются за ответ на hello
             let answer = client_connection.send_request("hello");
 
             if let Ok(response) = answer {
@@ -58,17 +95,153 @@ impl HostClient {
         Err("Handshake failed!".to_string())
     }
 
-    /// Отправка запроса на устройство, возврат полученного отклика
+    /// Отправка запроса на устройство, возврат отклика на него (см. `ReplyFilter::console_reply`)
     pub fn send_request(&mut self, request: &str) -> Result<String, String> {
-        let mut frame = MUFrame::new();
-        frame
-            .set_data(format!("{}{}", request, "\n").as_bytes().to_vec())
-            .map_err(|e| e.to_string())?;
-        crate::send_proto_message(frame, &mut self.serial_port)?;
+        self.send_request_matching(request, ReplyFilter::console_reply(request))
+    }
+
+    /// Отправка запроса на устройство, возврат отклика, соответствующего `filter`
+    /// (например, `ReplyFilter::prefix(Opcode::Console, b"soundvolume:")` для `get soundvolume`)
+    /// Консольные фреймы, не соответствующие `filter` (запоздавшие отклики), не принимаются за отклик
+    pub fn send_request_matching(
+        &mut self,
+        request: &str,
+        filter: ReplyFilter,
+    ) -> Result<String, String> {
+        let frame = MUFrame::console(&format!("{}{}", request, "\n"))?;
+        let reply = self.exchange_matching(frame, filter)?;
+
+        String::from_utf8(reply.get_data().to_vec()).map_err(|e| e.to_string())
+    }
+
+    /// Отправка команды на устройство с ожиданием подтверждения приема
+    pub fn send_command(&mut self, command: &str) -> Result<(), String> {
+        self.exchange(MUFrame::command(command)?, Opcode::Ack)?;
+        Ok(())
+    }
+
+    /// Запрос состояния лифта
+    pub fn request_status(&mut self) -> Result<ElevatorState, String> {
+        let reply = self.exchange(MUFrame::status_request(), Opcode::ElevatorState)?;
+
+        let state = String::from_utf8(reply.get_data().to_vec()).map_err(|e| e.to_string())?;
+        ElevatorState::parse_lenient(&state).map_err(|e| e.to_string())
+    }
+
+    /// Изменение максимального времени ожидания отклика на запрос
+    pub fn set_request_timeout(&mut self, timeout: Duration) {
+        self.request_timeout = timeout;
+    }
 
-        let new_frame =
-            crate::recv_proto_message(&mut self.serial_port).map_err(|e| e.to_string())?;
+    /// Максимальное время ожидания отклика на запрос
+    pub fn get_request_timeout(&self) -> Duration {
+        self.request_timeout
+    }
+
+    /// Получение фреймов, принятых без запроса, в порядке приема
+    pub fn take_unsolicited_frames(&mut self) -> Vec<MUFrame> {
+        self.dispatcher.take_unsolicited()
+    }
+
+    /// Ожидание фрейма, принятого без запроса (телеметрия и т.п.), не дольше `timeout`
+    /// Возвращает `None`, если за это время фрейм не получен
+    pub fn recv_unsolicited(&mut self, timeout: Duration) -> Result<Option<MUFrame>, String> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            if let Some(frame) = self.dispatcher.pop_unsolicited() {
+                return Ok(Some(frame));
+            }
+            match crate::recv_proto_message(&mut self.transport, &mut self.decoder, deadline) {
+                Ok(frame) => {
+                    self.dispatcher.dispatch(frame);
+                }
+                Err(e) if e == crate::RESPONSE_TIMEOUT => return Ok(None),
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Отправка произвольного фрейма (в том числе с двоичными данными), прием отклика с кодом операции `expected`
+    pub fn exchange(&mut self, frame: MUFrame, expected: Opcode) -> Result<MUFrame, String> {
+        self.exchange_matching(frame, ReplyFilter::opcode(expected))
+    }
+
+    /// Отправка произвольного фрейма, прием отклика, соответствующего `filter`
+    pub fn exchange_matching(
+        &mut self,
+        frame: MUFrame,
+        filter: ReplyFilter,
+    ) -> Result<MUFrame, String> {
+        let deadline = Instant::now() + self.request_timeout;
+        crate::send_proto_message(frame, &mut self.transport)?;
+
+        self.dispatcher.expect(filter);
+        crate::recv_proto_reply(
+            &mut self.transport,
+            &mut self.decoder,
+            &mut self.dispatcher,
+            deadline,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::MemoryTransport;
+    use std::io::{Read, Write};
+
+    /// Эмулятор интерфейсной платы: отвечает на консольные запросы, перед ответом передает телеметрию
+    /// На `get <name>` отвечает `<name>:0`, перед откликом передает запоздавший отклик на другой запрос
+    fn run_board(mut board: MemoryTransport) {
+        let mut decoder = MUFrameDecoder::new();
+        let mut buffer = [0; 256];
+        let telemetry = MUFrame::elevator_state("#STM:L1:R0:A0:S0:M0:E#\r\n").unwrap();
+        loop {
+            while let Some(frame) = decoder.next_frame() {
+                let reply = match frame.get_data() {
+                    b"hello\n" => "Hi!\r\n".to_string(),
+                    request => {
+                        let request = String::from_utf8_lossy(request);
+                        format!("{}:0", request.trim().trim_start_matches("get "))
+                    }
+                };
+                board.write_all(&telemetry.serialize()).unwrap();
+                board
+                    .write_all(&MUFrame::console("groupnumber:1").unwrap().serialize())
+                    .unwrap();
+                board
+                    .write_all(&MUFrame::console(&reply).unwrap().serialize())
+                    .unwrap();
+            }
+            match board.read(&mut buffer) {
+                Ok(0) => return,
+                Ok(received) => decoder.push(&buffer[..received]),
+                Err(_) => continue,
+            }
+        }
+    }
 
-        Ok(String::from_utf8(new_frame.get_data().to_vec()).map_err(|e| e.to_string())?)
+    #[test]
+    fn test_memory_transport_client() {
+        let (host, board) = MemoryTransport::pair();
+        let board = std::thread::spawn(move || run_board(board));
+
+        let mut client = HostClient::new(host, Duration::from_secs(1)).unwrap();
+        assert_eq!(
+            client.send_request("get soundvolume").unwrap(),
+            "soundvolume:0"
+        );
+        // Запоздавшие отклики отброшены, в очереди - только телеметрия
+        let frames = client.take_unsolicited_frames();
+        assert_eq!(frames.len(), 2);
+        assert!(
+            frames
+                .iter()
+                .all(|frame| frame.get_opcode() == Opcode::ElevatorState)
+        );
+
+        drop(client);
+        board.join().unwrap();
     }
 }
@@ -0,0 +1,199 @@
+//! # Распределение принятых фреймов между запросом и подписчиками
+//!
+//! > При включенном стриминге между запросом и откликом могут приходить фреймы телеметрии.
+//! > Отклик определяется по коду операции и, при необходимости, по началу данных
+//! > (номер последовательности для двоичных кодов операций, имя параметра для консоли).
+//! > Консольные фреймы - всегда отклики на запросы: не соответствующие ожидаемому отклику
+//! > (запоздавшие отклики на прежние запросы) отбрасываются.
+//! > Остальные фреймы помещаются в очередь фреймов, принятых без запроса.
+
+use std::collections::VecDeque;
+
+use log::{debug, trace, warn};
+
+use crate::mu_frame::MUFrame;
+use crate::opcode::Opcode;
+
+/// Признаки отклика на запрос
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplyFilter {
+    /// Код операции отклика
+    opcode: Opcode,
+    /// Обязательное начало данных отклика (пусто - любые данные)
+    prefix: Vec<u8>,
+}
+
+impl ReplyFilter {
+    /// Отклик - первый фрейм с кодом операции `opcode`
+    pub fn opcode(opcode: Opcode) -> Self {
+        ReplyFilter {
+            opcode,
+            prefix: Vec::new(),
+        }
+    }
+
+    /// Отклик - фрейм с кодом операции `opcode`, первый байт данных которого равен `sequence`
+    pub fn sequence(opcode: Opcode, sequence: u8) -> Self {
+        Self::prefix(opcode, &[sequence])
+    }
+
+    /// Отклик - фрейм с кодом операции `opcode`, данные которого начинаются с `prefix`
+    /// (например, `soundvolume:` для запроса `get soundvolume`)
+    pub fn prefix(opcode: Opcode, prefix: &[u8]) -> Self {
+        ReplyFilter {
+            opcode,
+            prefix: prefix.to_vec(),
+        }
+    }
+
+    /// Отклик на консольный запрос `request` - консольный фрейм, начинающийся с имени запрошенного значения:
+    /// `get/set/describe <name> ...` - `<name>:`, `hello` - `Hi!`, остальные запросы - `<запрос>:`
+    /// (например, `list:` для `list`)
+    pub fn console_reply(request: &str) -> Self {
+        let mut words = request.split_whitespace();
+        let prefix = match (words.next(), words.next()) {
+            (Some("hello"), _) => "Hi!".to_string(),
+            (Some("get" | "set" | "describe"), Some(name)) => format!("{name}:"),
+            (Some(command), _) => format!("{command}:"),
+            (None, _) => String::new(),
+        };
+        Self::prefix(Opcode::Console, prefix.as_bytes())
+    }
+
+    /// Проверка, является ли `frame` откликом
+    pub fn matches(&self, frame: &MUFrame) -> bool {
+        frame.get_opcode() == self.opcode && frame.get_data().starts_with(&self.prefix)
+    }
+}
+
+impl From<Opcode> for ReplyFilter {
+    fn from(opcode: Opcode) -> Self {
+        Self::opcode(opcode)
+    }
+}
+
+/// Распределитель принятых фреймов: отклик - ожидающему запросу, остальное - в очередь подписчика
+#[derive(Debug)]
+pub struct Dispatcher {
+    /// Признаки ожидаемого отклика (`None` - запрос не ожидает отклика)
+    expected: Option<ReplyFilter>,
+    /// Фреймы, принятые без запроса
+    unsolicited: VecDeque<MUFrame>,
+    /// Максимальный размер очереди, при переполнении отбрасываются самые старые фреймы
+    capacity: usize,
+}
+
+impl Dispatcher {
+    /// Создание распределителя с очередью фреймов, принятых без запроса, размером `capacity`
+    pub fn new(capacity: usize) -> Self {
+        Dispatcher {
+            expected: None,
+            unsolicited: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    /// Ожидание отклика, соответствующего `filter`
+    pub fn expect(&mut self, filter: ReplyFilter) {
+        self.expected = Some(filter);
+    }
+
+    /// Отмена ожидания отклика (например, по истечении срока): поздний консольный отклик будет отброшен,
+    /// отклик с другим кодом операции попадет в очередь
+    pub fn cancel(&mut self) {
+        self.expected = None;
+    }
+
+    /// Распределение принятого фрейма, возвращает фрейм, если он - ожидаемый отклик
+    pub fn dispatch(&mut self, frame: MUFrame) -> Option<MUFrame> {
+        if self
+            .expected
+            .as_ref()
+            .is_some_and(|filter| filter.matches(&frame))
+        {
+            self.expected = None;
+            return Some(frame);
+        }
+
+        let opcode = frame.get_opcode();
+        if opcode == Opcode::Console {
+            debug!(
+                "Stale console reply dropped: {:?}",
+                String::from_utf8_lossy(frame.get_data())
+            );
+            return None;
+        }
+        trace!("Unsolicited frame: {opcode}");
+        if let Opcode::Unknown(_) = opcode {
+            warn!("Received frame with unknown opcode: {opcode}");
+        }
+        if self.unsolicited.len() == self.capacity {
+            debug!("Unsolicited frames queue is full, the oldest frame is dropped");
+            self.unsolicited.pop_front();
+        }
+        if self.capacity > 0 {
+            self.unsolicited.push_back(frame);
+        }
+        None
+    }
+
+    /// Получение фреймов, принятых без запроса, в порядке приема
+    pub fn take_unsolicited(&mut self) -> Vec<MUFrame> {
+        self.unsolicited.drain(..).collect()
+    }
+
+    /// Получение самого старого фрейма, принятого без запроса
+    pub fn pop_unsolicited(&mut self) -> Option<MUFrame> {
+        self.unsolicited.pop_front()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dispatch() {
+        let telemetry = MUFrame::elevator_state("#STM:L2:R0:A0:S0:M1:E#\r\n").unwrap();
+        let stale = MUFrame::console("groupnumber:1\r\n").unwrap();
+        let reply = MUFrame::console("soundvolume:2\r\n").unwrap();
+
+        let mut dispatcher = Dispatcher::new(2);
+        dispatcher.expect(ReplyFilter::prefix(Opcode::Console, b"soundvolume:"));
+
+        assert_eq!(dispatcher.dispatch(telemetry.clone()), None);
+        assert_eq!(dispatcher.dispatch(stale.clone()), None);
+        assert_eq!(dispatcher.dispatch(reply.clone()), Some(reply.clone()));
+        // Отклик принят, следующий такой же фрейм - без запроса
+        assert_eq!(dispatcher.dispatch(reply.clone()), None);
+
+        // Запоздавшие консольные отклики отбрасываются, телеметрия остается в очереди
+        assert_eq!(dispatcher.take_unsolicited(), vec![telemetry]);
+        assert!(dispatcher.take_unsolicited().is_empty());
+        assert_eq!(dispatcher.dispatch(stale), None);
+        assert!(dispatcher.take_unsolicited().is_empty());
+    }
+
+    #[test]
+    fn test_console_reply_filter() {
+        let reply = |data: &str| MUFrame::console(data).unwrap();
+
+        assert!(ReplyFilter::console_reply("get soundvolume").matches(&reply("soundvolume:2")));
+        assert!(!ReplyFilter::console_reply("get soundvolume").matches(&reply("groupnumber:1")));
+        assert!(ReplyFilter::console_reply("set mode 1").matches(&reply("mode: 1")));
+        assert!(ReplyFilter::console_reply("describe floorlabel").matches(&reply("floorlabel:u8")));
+        assert!(ReplyFilter::console_reply("list").matches(&reply("list:a,b")));
+        assert!(ReplyFilter::console_reply("hello").matches(&reply("Hi!\r\n")));
+    }
+
+    #[test]
+    fn test_sequence_filter() {
+        let filter = ReplyFilter::sequence(Opcode::Unknown(0xE1), 7);
+        let reply = MUFrame::with_opcode(Opcode::Unknown(0xE1), vec![7, 0xFF]).unwrap();
+        let other = MUFrame::with_opcode(Opcode::Unknown(0xE1), vec![6, 0xFF]).unwrap();
+
+        assert!(filter.matches(&reply));
+        assert!(!filter.matches(&other));
+        assert!(ReplyFilter::from(Opcode::Ack).matches(&MUFrame::ack()));
+    }
+}
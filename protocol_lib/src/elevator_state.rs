@@ -0,0 +1,253 @@
+use log::warn;
+use std::str::FromStr;
+
+/// Начало строки состояния лифта
+const STATE_PREFIX: &str = "#STM:";
+/// Конец строки состояния лифта
+const STATE_SUFFIX: &str = "#\r\n";
+/// Поля строки состояния в порядке передачи
+const FIELDS: [char; 6] = ['L', 'R', 'A', 'S', 'M', 'E'];
+
+/// Ошибки разбора строки состояния лифта
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ElevatorStateError {
+    /// Строка не начинается с `#STM:`
+    #[error("Missing \"#STM:\" prefix")]
+    MissingPrefix,
+    /// Строка не заканчивается `#\r\n`
+    #[error("Missing \"#\\r\\n\" terminator")]
+    MissingTerminator,
+    /// Отсутствует обязательное поле
+    #[error("Missing field {0}")]
+    MissingField(char),
+    /// Поле встречается несколько раз
+    #[error("Duplicate field {0}")]
+    DuplicateField(char),
+    /// Поле не на своем месте или неизвестное поле
+    #[error("Unexpected field \"{0}\"")]
+    UnexpectedField(String),
+    /// Значение поля не является числом допустимого диапазона
+    #[error("Bad value \"{value}\" of field {field}")]
+    BadValue { field: char, value: String },
+}
+
+/// Состояние лифта, передаваемое с кодом операции 0xDA
+///
+/// Формат строки: `#STM:L%d:R%d:A%d:S%d:M%d:E%d#\r\n`, значение поля `E` отсутствует при отсутствии ошибки
+///
+/// ## Пример
+/// ```ignore
+/// let state = ElevatorState::parse("#STM:L10:R3:A1:S4:M0:E#\r\n")?;
+/// assert_eq!(state.floor, 10);
+/// assert_eq!(state.to_wire_string(), "#STM:L10:R3:A1:S4:M0:E#\r\n");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ElevatorState {
+    /// `L` - номер этажа
+    pub floor: i32,
+    /// `R` - код направления движения
+    pub direction: u8,
+    /// `A` - код прибытия на этаж
+    pub arrival: u8,
+    /// `S` - номер звукового сообщения
+    pub sound: u8,
+    /// `M` - код режима работы
+    pub mode: u8,
+    /// `E` - код ошибки, `None` - ошибки нет
+    pub error: Option<u8>,
+}
+
+impl ElevatorState {
+    /// ## Строгий разбор строки состояния
+    /// Строка должна в точности соответствовать формату: поля по порядку, окончание `#\r\n`
+    pub fn parse(state: &str) -> Result<Self, ElevatorStateError> {
+        let body = state
+            .strip_prefix(STATE_PREFIX)
+            .ok_or(ElevatorStateError::MissingPrefix)?;
+        let body = body
+            .strip_suffix(STATE_SUFFIX)
+            .ok_or(ElevatorStateError::MissingTerminator)?;
+
+        let mut fields = body.split(':');
+        let mut result = ElevatorState::default();
+        for field in FIELDS {
+            let Some(item) = fields.next() else {
+                return Err(ElevatorStateError::MissingField(field));
+            };
+            let Some(value) = item.strip_prefix(field) else {
+                return Err(ElevatorStateError::UnexpectedField(item.to_string()));
+            };
+            result.set_field(field, value)?;
+        }
+        if let Some(item) = fields.next() {
+            return Err(ElevatorStateError::UnexpectedField(item.to_string()));
+        }
+
+        Ok(result)
+    }
+
+    /// ## Нестрогий разбор строки состояния
+    /// Допускаются пробелы и символы `\0` по краям, отсутствие `\r\n`, поля в любом порядке
+    /// и в нижнем регистре; неизвестные поля пропускаются
+    pub fn parse_lenient(state: &str) -> Result<Self, ElevatorStateError> {
+        let state = state.trim_matches(|c: char| c.is_whitespace() || c == '\0');
+        let body = state
+            .get(..STATE_PREFIX.len())
+            .filter(|prefix| prefix.eq_ignore_ascii_case(STATE_PREFIX))
+            .map(|_| &state[STATE_PREFIX.len()..])
+            .ok_or(ElevatorStateError::MissingPrefix)?;
+        let body = body
+            .strip_suffix('#')
+            .ok_or(ElevatorStateError::MissingTerminator)?;
+
+        let mut result = ElevatorState::default();
+        let mut parsed: Vec<char> = Vec::new();
+        for item in body
+            .split(':')
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+        {
+            let mut chars = item.chars();
+            let Some(field) = chars.next().map(|c| c.to_ascii_uppercase()) else {
+                continue;
+            };
+            if !FIELDS.contains(&field) {
+                warn!("Elevator state: unknown field \"{item}\" skipped");
+                continue;
+            }
+            if parsed.contains(&field) {
+                return Err(ElevatorStateError::DuplicateField(field));
+            }
+            result.set_field(field, chars.as_str().trim())?;
+            parsed.push(field);
+        }
+        if let Some(field) = FIELDS.into_iter().find(|field| !parsed.contains(field)) {
+            return Err(ElevatorStateError::MissingField(field));
+        }
+
+        Ok(result)
+    }
+
+    /// ## Формирование строки состояния для передачи (`#STM:...:E#\r\n`)
+    pub fn to_wire_string(&self) -> String {
+        let error = self
+            .error
+            .map(|error| error.to_string())
+            .unwrap_or_default();
+        format!(
+            "{STATE_PREFIX}L{}:R{}:A{}:S{}:M{}:E{error}{STATE_SUFFIX}",
+            self.floor, self.direction, self.arrival, self.sound, self.mode
+        )
+    }
+
+    /// Запись значения поля `field`
+    fn set_field(&mut self, field: char, value: &str) -> Result<(), ElevatorStateError> {
+        let bad_value = || ElevatorStateError::BadValue {
+            field,
+            value: value.to_string(),
+        };
+        match field {
+            'L' => self.floor = value.parse().map_err(|_| bad_value())?,
+            'R' => self.direction = value.parse().map_err(|_| bad_value())?,
+            'A' => self.arrival = value.parse().map_err(|_| bad_value())?,
+            'S' => self.sound = value.parse().map_err(|_| bad_value())?,
+            'M' => self.mode = value.parse().map_err(|_| bad_value())?,
+            'E' if value.is_empty() => self.error = None,
+            'E' => self.error = Some(value.parse().map_err(|_| bad_value())?),
+            _ => {
+                return Err(ElevatorStateError::UnexpectedField(format!(
+                    "{field}{value}"
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for ElevatorState {
+    type Err = ElevatorStateError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_strict() {
+        let state = ElevatorState::parse("#STM:L10:R3:A1:S4:M0:E#\r\n").unwrap();
+        assert_eq!(
+            state,
+            ElevatorState {
+                floor: 10,
+                direction: 3,
+                arrival: 1,
+                sound: 4,
+                mode: 0,
+                error: None,
+            }
+        );
+        assert_eq!(state.to_wire_string(), "#STM:L10:R3:A1:S4:M0:E#\r\n");
+
+        let state: ElevatorState = "#STM:L-1:R16:A0:S0:M2:E7#\r\n".parse().unwrap();
+        assert_eq!(state.floor, -1);
+        assert_eq!(state.error, Some(7));
+        assert_eq!(state.to_wire_string(), "#STM:L-1:R16:A0:S0:M2:E7#\r\n");
+
+        assert_eq!(
+            ElevatorState::parse("STM:L10:R3:A1:S4:M0:E#\r\n"),
+            Err(ElevatorStateError::MissingPrefix)
+        );
+        assert_eq!(
+            ElevatorState::parse("#STM:L10:R3:A1:S4:M0:E#"),
+            Err(ElevatorStateError::MissingTerminator)
+        );
+        assert_eq!(
+            ElevatorState::parse("#STM:L10:R3:A1:S4:M0#\r\n"),
+            Err(ElevatorStateError::MissingField('E'))
+        );
+        assert_eq!(
+            ElevatorState::parse("#STM:L10:A1:R3:S4:M0:E#\r\n"),
+            Err(ElevatorStateError::UnexpectedField("A1".to_string()))
+        );
+        assert_eq!(
+            ElevatorState::parse("#STM:L10:R3:A1:S999:M0:E#\r\n"),
+            Err(ElevatorStateError::BadValue {
+                field: 'S',
+                value: "999".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_lenient() {
+        let expected = ElevatorState::parse("#STM:L16:R16:A1:S2:M0:E#\r\n").unwrap();
+
+        assert_eq!(
+            ElevatorState::parse_lenient("#STM:L16:R16:A1:S2:M0:E#\r\n\0"),
+            Ok(expected)
+        );
+        assert_eq!(
+            ElevatorState::parse_lenient(" #stm:m0:l16:r16:a1:s2:e:X5# "),
+            Ok(expected)
+        );
+        assert_eq!(
+            ElevatorState::parse_lenient("#STM:L16:L15:R16:A1:S2:M0:E#"),
+            Err(ElevatorStateError::DuplicateField('L'))
+        );
+        assert_eq!(
+            ElevatorState::parse_lenient("#STM:L16:R16:A1:S2:E#"),
+            Err(ElevatorStateError::MissingField('M'))
+        );
+        assert_eq!(
+            ElevatorState::parse_lenient("#STM:Lx:R16:A1:S2:M0:E#"),
+            Err(ElevatorStateError::BadValue {
+                field: 'L',
+                value: "x".to_string()
+            })
+        );
+    }
+}
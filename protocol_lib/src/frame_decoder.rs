@@ -0,0 +1,203 @@
+use log::trace;
+
+use crate::mu_frame::{FRAME_OVERHEAD, MUFrame, SYNC1};
+
+/// Потоковый декодер фреймов протокола "МЮ"
+///
+/// Принимает данные порциями произвольного размера (`push`) и выдает целые фреймы (`next_frame`).
+/// Байты до `SYNC1`, а также фреймы с неверным `SYNC2` или CRC отбрасываются,
+/// поиск следующего фрейма продолжается со следующего за отброшенным `SYNC1` байта.
+/// Незавершенный фрейм отбрасывается, если за его началом уже принят целый фрейм
+/// (ложный `SYNC1` в шуме с большим значением size).
+///
+/// ## Пример
+/// ```ignore
+/// let mut decoder = MUFrameDecoder::new();
+/// decoder.push(&chunk);
+/// while let Some(frame) = decoder.next_frame() {
+///     // обработка фрейма
+/// }
+/// ```
+#[derive(Debug, Default)]
+pub struct MUFrameDecoder {
+    /// Принятые, но еще не разобранные байты
+    buffer: Vec<u8>,
+    /// Количество отброшенных байтов
+    discarded_bytes: usize,
+}
+
+impl MUFrameDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Добавление очередной порции принятых байтов
+    pub fn push(&mut self, bytes: &[u8]) {
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    /// Получение следующего целого фрейма, `None` - фрейм еще не принят полностью
+    pub fn next_frame(&mut self) -> Option<MUFrame> {
+        loop {
+            // Поиск начала фрейма
+            match self.buffer.iter().position(|byte| *byte == SYNC1) {
+                Some(0) => {}
+                Some(start) => self.discard(start),
+                None => {
+                    self.discard(self.buffer.len());
+                    return None;
+                }
+            }
+
+            // Ожидание поля size
+            let &length = self.buffer.get(1)?;
+
+            // Ожидание фрейма целиком
+            let frame_length = FRAME_OVERHEAD + length as usize;
+            if self.buffer.len() < frame_length {
+                // Ложный SYNC1 (шум) с большим значением size: если за ним уже принят целый фрейм,
+                // ожидание прекращается и начало буфера отбрасывается
+                match self.find_complete_frame() {
+                    Some(start) => {
+                        trace!("Frame decoder: false SYNC1, resynchronization");
+                        self.discard(start);
+                        continue;
+                    }
+                    None => return None,
+                }
+            }
+
+            match MUFrame::deserialize(&self.buffer[..frame_length]) {
+                Ok(frame) => {
+                    self.buffer.drain(..frame_length);
+                    return Some(frame);
+                }
+                Err(e) => {
+                    // Ложный SYNC1 или поврежденный фрейм: поиск со следующего байта
+                    trace!("Frame decoder: {e}, resynchronization");
+                    self.discard(1);
+                }
+            }
+        }
+    }
+
+    /// Количество байтов, отброшенных при поиске фреймов
+    pub fn get_discarded_bytes(&self) -> usize {
+        self.discarded_bytes
+    }
+
+    /// Количество принятых, но еще не разобранных байтов
+    pub fn get_buffered_bytes(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Поиск целого фрейма с верными SYNC2 и CRC, начинающегося не с первого байта буфера
+    fn find_complete_frame(&self) -> Option<usize> {
+        (1..self.buffer.len())
+            .filter(|&start| self.buffer[start] == SYNC1)
+            .find(|&start| {
+                let Some(&length) = self.buffer.get(start + 1) else {
+                    return false;
+                };
+                let end = start + FRAME_OVERHEAD + length as usize;
+                end <= self.buffer.len() && MUFrame::deserialize(&self.buffer[start..end]).is_ok()
+            })
+    }
+
+    /// Отбрасывание `count` байтов из начала буфера
+    fn discard(&mut self, count: usize) {
+        self.buffer.drain(..count);
+        self.discarded_bytes += count;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn create_frame_bytes(data: &[u8]) -> Vec<u8> {
+        let mut frame = MUFrame::new();
+        frame.set_data(data.to_vec()).unwrap();
+        frame.serialize()
+    }
+
+    #[test]
+    fn test_split_chunks() {
+        let bytes = create_frame_bytes(b"get soundvolume\n");
+        let mut decoder = MUFrameDecoder::new();
+
+        for chunk in bytes.chunks(3) {
+            assert!(decoder.next_frame().is_none());
+            decoder.push(chunk);
+        }
+
+        let frame = decoder.next_frame().unwrap();
+        assert_eq!(frame.get_data(), b"get soundvolume\n");
+        assert_eq!(decoder.get_discarded_bytes(), 0);
+        assert_eq!(decoder.get_buffered_bytes(), 0);
+    }
+
+    #[test]
+    fn test_garbage_and_several_frames() {
+        let first = create_frame_bytes(b"soundvolume:2");
+        let second = create_frame_bytes(b"musicvolume:0");
+
+        let mut corrupted = create_frame_bytes(b"groupnumber:1");
+        let crc_index = corrupted.len() - 2;
+        corrupted[crc_index] ^= 0xFF;
+
+        let mut bytes = vec![0x00, 0x13, SYNC1, 0x37];
+        bytes.extend(&first);
+        bytes.extend(&corrupted);
+        bytes.extend(&second);
+        bytes.extend(&first[..4]);
+
+        let mut decoder = MUFrameDecoder::new();
+        decoder.push(&bytes);
+
+        assert_eq!(decoder.next_frame().unwrap().get_data(), b"soundvolume:2");
+        assert_eq!(decoder.next_frame().unwrap().get_data(), b"musicvolume:0");
+        assert!(decoder.next_frame().is_none());
+
+        assert_eq!(decoder.get_discarded_bytes(), 4 + corrupted.len());
+        assert_eq!(decoder.get_buffered_bytes(), 4);
+    }
+
+    #[test]
+    fn test_false_sync_with_large_size() {
+        let reply = create_frame_bytes(b"soundvolume:2");
+
+        let mut bytes = vec![SYNC1, 0xF0];
+        bytes.extend(&reply);
+
+        let mut decoder = MUFrameDecoder::new();
+        decoder.push(&bytes);
+
+        assert_eq!(decoder.next_frame().unwrap().get_data(), b"soundvolume:2");
+        assert_eq!(decoder.get_discarded_bytes(), 2);
+        assert_eq!(decoder.get_buffered_bytes(), 0);
+
+        // Незавершенный фрейм без целого фрейма за ним ожидается
+        decoder.push(&reply[..reply.len() - 1]);
+        assert!(decoder.next_frame().is_none());
+        decoder.push(&reply[reply.len() - 1..]);
+        assert_eq!(decoder.next_frame().unwrap().get_data(), b"soundvolume:2");
+    }
+
+    proptest! {
+        #[test]
+        fn prop_decoder_never_panics(
+            chunks in proptest::collection::vec(proptest::collection::vec(any::<u8>(), 0..64), 0..16),
+        ) {
+            let mut decoder = MUFrameDecoder::new();
+            let mut received = 0;
+            for chunk in chunks.iter() {
+                decoder.push(chunk);
+                received += chunk.len();
+                while decoder.next_frame().is_some() {}
+            }
+            prop_assert!(decoder.get_discarded_bytes() + decoder.get_buffered_bytes() <= received);
+        }
+    }
+}
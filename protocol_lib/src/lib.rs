@@ -1,70 +1,103 @@
+#[cfg(feature = "async")]
+pub mod async_client;
 pub mod client;
-mod mu_frame;
-
+pub mod dispatcher;
+pub mod elevator_state;
+pub mod frame_decoder;
+pub mod mu_frame;
+pub mod opcode;
+pub mod telemetry;
+pub mod transport;
+
+use crate::dispatcher::Dispatcher;
+use crate::frame_decoder::MUFrameDecoder;
 use crate::mu_frame::MUFrame;
 
 use log::trace;
 use std::{
-    io::{Read, Write},
+    io::{ErrorKind, Read, Write},
     thread,
+    time::{Duration, Instant},
 };
 
-/// Задержка перед приемом отклика от интерфейсной платы
-const ANSWER_DELAY_MS: u64 = 500;
+/// Максимальное время ожидания одной порции данных: с этим периодом проверяется срок ожидания отклика
+pub(crate) const READ_POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Ошибка приема: срок ожидания отклика истек
+pub(crate) const RESPONSE_TIMEOUT: &str = "Response timeout";
 
 /// Отправка сообщения
 fn send_proto_message<Writer: Write>(data: MUFrame, mut writer: Writer) -> Result<(), String> {
     let bytes = data.serialize();
     writer.write_all(&bytes).map_err(|e| e.to_string())?;
-
-    // Время для составления ответа
-    thread::sleep(std::time::Duration::from_millis(ANSWER_DELAY_MS));
-
-    Ok(())
+    writer.flush().map_err(|e| e.to_string())
 }
 
 /// Прием сообщения
-fn recv_proto_message<Reader: Read>(mut reader: Reader) -> Result<MUFrame, String> {
-    let mut raw_frame = Vec::new();
+/// Данные читаются порциями до получения целого фрейма или до наступления срока `deadline`;
+/// байты, принятые после фрейма, остаются в `decoder` до следующего вызова
+fn recv_proto_message<Reader: Read>(
+    mut reader: Reader,
+    decoder: &mut MUFrameDecoder,
+    deadline: Instant,
+) -> Result<MUFrame, String> {
     let mut read_buffer = [0; 256];
 
-    // Чтение отклика от интерфейсной платы
-    reader.read(&mut read_buffer).map_err(|e| e.to_string())?;
-
-    trace!("Received raw message: {read_buffer:?}");
-
-    // Сборка сообщения
-    let prefix = read_buffer[0];
-    raw_frame.push(prefix);
-
-    let payload_length = read_buffer[1];
-    raw_frame.push(payload_length);
-
-    let opcode = read_buffer[2];
-    raw_frame.push(opcode);
-
-    // 3[prefix, payload_length, opcode] + payload_length
-    let end_of_data_idx = 3 + payload_length as usize;
-
-    let payload = &read_buffer[3..end_of_data_idx];
-    raw_frame.extend_from_slice(payload);
-
-    let crc_low = read_buffer[end_of_data_idx];
-    raw_frame.push(crc_low);
-    let crc_high = read_buffer[1 + end_of_data_idx];
-    raw_frame.push(crc_high);
-
-    let postfix = read_buffer[2 + end_of_data_idx];
-    raw_frame.push(postfix);
-
-    let frame = MUFrame::deserialize(&raw_frame).map_err(|e| e.to_string())?;
+    loop {
+        if let Some(frame) = decoder.next_frame() {
+            return Ok(frame);
+        }
+        if Instant::now() >= deadline {
+            return Err(RESPONSE_TIMEOUT.to_string());
+        }
+
+        // Чтение отклика от интерфейсной платы
+        let received = match reader.read(&mut read_buffer) {
+            Ok(0) => return Err("Connection closed".to_string()),
+            Ok(received) => received,
+            // Данных пока нет: ожидание продолжается до наступления срока
+            Err(e) if matches!(e.kind(), ErrorKind::TimedOut | ErrorKind::Interrupted) => continue,
+            Err(e) if e.kind() == ErrorKind::WouldBlock => {
+                thread::sleep(READ_POLL_INTERVAL);
+                continue;
+            }
+            Err(e) => return Err(e.to_string()),
+        };
+
+        trace!("Received raw message: {:?}", &read_buffer[..received]);
+
+        decoder.push(&read_buffer[..received]);
+    }
+}
 
-    Ok(frame)
+/// Прием отклика, ожидаемого `dispatcher`, до наступления срока `deadline`
+/// Фреймы, принятые до отклика (телеметрия, подтверждения и т.п.), остаются в очереди `dispatcher`
+fn recv_proto_reply<Reader: Read>(
+    mut reader: Reader,
+    decoder: &mut MUFrameDecoder,
+    dispatcher: &mut Dispatcher,
+    deadline: Instant,
+) -> Result<MUFrame, String> {
+    loop {
+        let frame = match recv_proto_message(&mut reader, decoder, deadline) {
+            Ok(frame) => frame,
+            Err(e) => {
+                dispatcher.cancel();
+                return Err(e);
+            }
+        };
+        if let Some(reply) = dispatcher.dispatch(frame) {
+            return Ok(reply);
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::dispatcher::ReplyFilter;
+    use crate::opcode::Opcode;
+    use std::collections::VecDeque;
 
     #[test]
     fn test_send_and_recv() {
@@ -77,8 +110,82 @@ mod tests {
 
         send_proto_message(frame_to_send.clone(), &mut buf).unwrap();
 
-        let received_frame = recv_proto_message(&buf[..]).unwrap();
+        let mut decoder = MUFrameDecoder::new();
+        let deadline = Instant::now() + Duration::from_secs(1);
+        let received_frame = recv_proto_message(&buf[..], &mut decoder, deadline).unwrap();
         assert_eq!(received_frame.get_data(), frame_to_send.get_data());
         assert_eq!(received_frame, frame_to_send);
     }
+
+    #[test]
+    fn test_recv_reply_dispatch() {
+        let state = MUFrame::elevator_state("#STM:L1:R16:A0:S0:M0:E#\r\n").unwrap();
+        let reply = MUFrame::console("soundvolume:2\r\n").unwrap();
+
+        let mut buf = Vec::new();
+        for frame in [&state, &MUFrame::ack(), &reply, &state] {
+            buf.extend(frame.serialize());
+        }
+
+        let mut decoder = MUFrameDecoder::new();
+        let mut dispatcher = Dispatcher::new(8);
+        dispatcher.expect(ReplyFilter::opcode(Opcode::Console));
+        let deadline = Instant::now() + Duration::from_secs(1);
+        let received = recv_proto_reply(&buf[..], &mut decoder, &mut dispatcher, deadline).unwrap();
+
+        assert_eq!(received, reply);
+        assert_eq!(
+            dispatcher.take_unsolicited(),
+            [state.clone(), MUFrame::ack()]
+        );
+        // Фрейм, принятый после отклика, остается в декодере
+        assert_eq!(decoder.next_frame(), Some(state));
+    }
+
+    /// Источник данных, выдающий порции с задержками, как последовательный порт с таймаутом чтения
+    struct SlowReader {
+        chunks: VecDeque<Option<Vec<u8>>>,
+    }
+
+    impl Read for SlowReader {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            match self.chunks.pop_front() {
+                Some(Some(chunk)) => {
+                    buf[..chunk.len()].copy_from_slice(&chunk);
+                    Ok(chunk.len())
+                }
+                _ => {
+                    thread::sleep(READ_POLL_INTERVAL);
+                    Err(ErrorKind::TimedOut.into())
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_recv_deadline() {
+        let bytes = MUFrame::console("soundvolume:2\r\n").unwrap().serialize();
+        let (head, tail) = bytes.split_at(5);
+        let mut reader = SlowReader {
+            chunks: VecDeque::from([None, Some(head.to_vec()), None, Some(tail.to_vec())]),
+        };
+
+        // Отклик принимается сразу по получении целого фрейма
+        let started = Instant::now();
+        let mut decoder = MUFrameDecoder::new();
+        let frame = recv_proto_message(&mut reader, &mut decoder, started + Duration::from_secs(5))
+            .unwrap();
+        assert_eq!(frame.get_data(), b"soundvolume:2\r\n");
+        assert!(started.elapsed() < Duration::from_secs(1));
+
+        // Отклик не получен до наступления срока
+        let started = Instant::now();
+        let result = recv_proto_message(
+            &mut reader,
+            &mut decoder,
+            started + Duration::from_millis(100),
+        );
+        assert_eq!(result, Err("Response timeout".to_string()));
+        assert!(started.elapsed() >= Duration::from_millis(100));
+    }
 }
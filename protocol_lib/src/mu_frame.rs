@@ -1,9 +1,41 @@
 use std::fmt::Display;
 
-const SYNC1: u8 = 0xAA;
+use crate::opcode::Opcode;
+
+pub(crate) const SYNC1: u8 = 0xAA;
 const SYNC2: u8 = 0xBB;
 const MAX_DATA_SIZE: u8 = u8::MAX;
-const CONSOLE_OPCODE: u8 = 0xC0;
+/// Размер служебных полей фрейма: sync1, size, opcode, crc16 (2 байта), sync2
+pub(crate) const FRAME_OVERHEAD: usize = 6;
+
+/// Ошибки разбора и построения фрейма
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum FrameError {
+    /// Фрейм принят не полностью
+    #[error("Truncated frame: {needed} bytes needed, {got} received")]
+    Truncated { needed: usize, got: usize },
+    /// Первый байт не равен SYNC1
+    #[error("Bad prefix")]
+    BadPrefix,
+    /// Последний байт не равен SYNC2
+    #[error("Bad suffix")]
+    BadSuffix,
+    /// Принятый CRC не совпадает с вычисленным
+    #[error("Bad CRC: expected {expected:#06X}, got {got:#06X}")]
+    BadCrc { expected: u16, got: u16 },
+    /// После фрейма есть лишние байты
+    #[error("Trailing bytes after frame")]
+    TrailingBytes,
+    /// Данные текстового фрейма содержат не ASCII-символы
+    #[error("Bad encoding")]
+    BadEncoding,
+    /// Данные фрейма пусты (фреймы без данных создаются `MUFrame::ack`, `MUFrame::status_request`)
+    #[error("Data is empty")]
+    EmptyData,
+    /// Данные не помещаются в фрейм
+    #[error("Data too long: {0} bytes, max {MAX_DATA_SIZE}")]
+    DataTooLong(usize),
+}
 
 /// Пакет данных протокола "МЮ"
 ///
@@ -14,8 +46,9 @@ const CONSOLE_OPCODE: u8 = 0xC0;
 ///       frame_to_send
 ///       .set_data(b"get server_info\n".to_vec())?;
 /// let raw_bytes = frame_to_send.serialize();
-/// ```
 ///
+/// let status_request = MUFrame::status_request();
+/// ```
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct MUFrame {
@@ -33,7 +66,7 @@ impl MUFrame {
         Self {
             prefix: SYNC1,
             length: 0,
-            opcode: CONSOLE_OPCODE,
+            opcode: Opcode::Console.into(),
             data: Vec::with_capacity(MAX_DATA_SIZE as usize),
             crc_low: 0x00,
             crc_high: 0x00,
@@ -41,42 +74,125 @@ impl MUFrame {
         }
     }
 
-    pub(crate) fn get_data(&self) -> &Vec<u8> {
+    /// Фрейм консоли с текстовым запросом (ответом) `text`
+    pub fn console(text: &str) -> Result<Self, String> {
+        Self::with_text(Opcode::Console, text)
+    }
+
+    /// Фрейм с данными о состоянии лифта (`#STM:...:E#\r\n`)
+    pub fn elevator_state(state: &str) -> Result<Self, String> {
+        Self::with_text(Opcode::ElevatorState, state)
+    }
+
+    /// Фрейм команды от компьютера к микроконтроллеру
+    pub fn command(command: &str) -> Result<Self, String> {
+        Self::with_text(Opcode::Command, command)
+    }
+
+    /// Фрейм подтверждения приема (без данных)
+    pub fn ack() -> Self {
+        Self::empty(Opcode::Ack)
+    }
+
+    /// Фрейм запроса состояния (без данных)
+    pub fn status_request() -> Self {
+        Self::empty(Opcode::StatusRequest)
+    }
+
+    /// Код операции фрейма
+    pub fn get_opcode(&self) -> Opcode {
+        Opcode::from(self.opcode)
+    }
+
+    /// Данные фрейма
+    pub fn get_data(&self) -> &[u8] {
         &self.data
     }
 
+    /// Фрейм с произвольным кодом операции `opcode` и непустыми данными `data`
+    /// Данные текстовых кодов операций должны состоять из ASCII-символов, остальных - произвольные байты
+    pub fn with_opcode(opcode: Opcode, data: Vec<u8>) -> Result<Self, String> {
+        let mut frame = Self::new();
+        frame.opcode = opcode.into();
+        frame.set_data(data).map_err(|e| e.to_string())?;
+        Ok(frame)
+    }
+
+    /// Создание фрейма `opcode` с текстовыми данными
+    fn with_text(opcode: Opcode, text: &str) -> Result<Self, String> {
+        Self::with_opcode(opcode, text.as_bytes().to_vec())
+    }
+
+    /// Создание фрейма `opcode` без данных
+    fn empty(opcode: Opcode) -> Self {
+        let mut frame = Self::new();
+        frame.opcode = opcode.into();
+        frame.update_crc();
+        frame
+    }
+
     /// Загрузка данных в фрейм, вычисление CRC и длины
-    pub(crate) fn set_data(&mut self, data: Vec<u8>) -> Result<(), String> {
-        if data.len() > MAX_DATA_SIZE as usize || data.is_empty() {
-            return Err("Data too long".to_string());
+    pub(crate) fn set_data(&mut self, data: Vec<u8>) -> Result<(), FrameError> {
+        if data.is_empty() {
+            return Err(FrameError::EmptyData);
+        }
+        if data.len() > MAX_DATA_SIZE as usize {
+            return Err(FrameError::DataTooLong(data.len()));
         }
 
-        if !data.is_ascii() {
-            return Err("Bad encoding".to_string());
+        if self.get_opcode().is_text() && !data.is_ascii() {
+            return Err(FrameError::BadEncoding);
         }
 
         self.length = data.len() as u8;
         self.data = data;
+        self.update_crc();
+
+        Ok(())
+    }
+
+    /// Вычисление и запись CRC
+    fn update_crc(&mut self) {
         let crc_value = self.calculate_src();
         self.crc_low = crc_value as u8;
         self.crc_high = (crc_value >> 8) as u8;
-
-        Ok(())
     }
 
     /// Десериализация данных из буфера
-    pub(crate) fn deserialize(data: &[u8]) -> Result<Self, String> {
+    /// Буфер должен содержать ровно один фрейм
+    pub(crate) fn deserialize(data: &[u8]) -> Result<Self, FrameError> {
+        let Some(&prefix) = data.first() else {
+            return Err(FrameError::Truncated {
+                needed: FRAME_OVERHEAD,
+                got: 0,
+            });
+        };
+        if prefix != SYNC1 {
+            return Err(FrameError::BadPrefix);
+        }
+
+        let needed = match data.get(1) {
+            Some(&length) => FRAME_OVERHEAD + length as usize,
+            None => FRAME_OVERHEAD,
+        };
+        if data.len() < needed {
+            return Err(FrameError::Truncated {
+                needed,
+                got: data.len(),
+            });
+        }
+        if data.len() > needed {
+            return Err(FrameError::TrailingBytes);
+        }
+
         let mut frame = Self::new();
-        frame.prefix = data[0];
+        frame.prefix = prefix;
         frame.length = data[1];
         frame.opcode = data[2];
-
-        frame.data = data[3..3 + frame.length as usize].to_vec();
-
-        frame.crc_low = data[3 + frame.length as usize];
-        frame.crc_high = data[4 + frame.length as usize];
-
-        frame.suffix = data[5 + frame.length as usize];
+        frame.data = data[3..needed - 3].to_vec();
+        frame.crc_low = data[needed - 3];
+        frame.crc_high = data[needed - 2];
+        frame.suffix = data[needed - 1];
 
         frame.invalidate_frame()?;
 
@@ -98,21 +214,24 @@ impl MUFrame {
     }
 
     /// Проверка валидности фрейма
-    fn invalidate_frame(&self) -> Result<(), String> {
+    fn invalidate_frame(&self) -> Result<(), FrameError> {
         if !self.is_prefix_correct() {
-            return Err("Bad prefix".to_string());
+            return Err(FrameError::BadPrefix);
         }
         if !self.is_postfix_correct() {
-            return Err("Bad postfix".to_string());
+            return Err(FrameError::BadSuffix);
         }
 
         let crc_value = (self.crc_high as u16) << 8 | self.crc_low as u16;
         if !self.is_crc_valid(crc_value) {
-            return Err("Bad CRC".to_string());
+            return Err(FrameError::BadCrc {
+                expected: self.calculate_src(),
+                got: crc_value,
+            });
         }
 
-        if !self.data.is_ascii() {
-            return Err("Bad encoding".to_string());
+        if self.get_opcode().is_text() && !self.data.is_ascii() {
+            return Err(FrameError::BadEncoding);
         }
 
         Ok(())
@@ -146,12 +265,18 @@ impl MUFrame {
     }
 }
 
+impl Default for MUFrame {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Display for MUFrame {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
             "MU Message: \n 1.Opcode={}, \n 2.Data length={} \n 3.Payload={:?} \n 4.CRC={}",
-            self.opcode,
+            self.get_opcode(),
             self.length,
             self.data,
             (self.crc_high as u16) << 8 | self.crc_low as u16
@@ -162,6 +287,7 @@ impl Display for MUFrame {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
     #[test]
     fn test_check_crc_calculation() {
         let mut frame = MUFrame::new();
@@ -203,7 +329,7 @@ mod tests {
         assert!(frame.is_prefix_correct());
         assert!(frame.is_postfix_correct());
         assert!(frame.is_crc_valid(0x7780));
-        assert_eq!(frame.opcode, CONSOLE_OPCODE);
+        assert_eq!(frame.get_opcode(), Opcode::Console);
         assert_eq!(frame.data, b"#STM:L0:R16:A1:S0:M0:E#\r\n\0");
     }
 
@@ -218,8 +344,145 @@ mod tests {
         assert!(frame.is_prefix_correct());
         assert!(frame.is_postfix_correct());
         assert!(frame.is_crc_valid(0xB6BB));
-        assert_eq!(frame.opcode, CONSOLE_OPCODE);
+        assert_eq!(frame.get_opcode(), Opcode::Console);
         assert_eq!(frame.data, b"#STM:L16:R16:A1:S2:M0:E#\r\n\0");
         frame.invalidate_frame().unwrap();
     }
+
+    #[test]
+    fn test_deserialize_errors() {
+        let mut frame = MUFrame::new();
+        frame.set_data(b"get soundvolume\n".to_vec()).unwrap();
+        let bytes = frame.serialize();
+
+        assert_eq!(
+            MUFrame::deserialize(&[]),
+            Err(FrameError::Truncated { needed: 6, got: 0 })
+        );
+        assert_eq!(
+            MUFrame::deserialize(&bytes[..10]),
+            Err(FrameError::Truncated {
+                needed: bytes.len(),
+                got: 10
+            })
+        );
+        assert_eq!(
+            MUFrame::deserialize(&bytes[1..]),
+            Err(FrameError::BadPrefix)
+        );
+
+        let mut with_trailing = bytes.clone();
+        with_trailing.push(0x00);
+        assert_eq!(
+            MUFrame::deserialize(&with_trailing),
+            Err(FrameError::TrailingBytes)
+        );
+
+        let mut bad_suffix = bytes.clone();
+        *bad_suffix.last_mut().unwrap() = 0x00;
+        assert_eq!(
+            MUFrame::deserialize(&bad_suffix),
+            Err(FrameError::BadSuffix)
+        );
+
+        let mut bad_crc = bytes.clone();
+        let crc_index = bad_crc.len() - 3;
+        bad_crc[crc_index] ^= 0x01;
+        assert!(matches!(
+            MUFrame::deserialize(&bad_crc),
+            Err(FrameError::BadCrc { .. })
+        ));
+    }
+
+    #[test]
+    fn test_frame_kinds() {
+        let command = MUFrame::command("reboot").unwrap();
+        assert_eq!(command.get_opcode(), Opcode::Command);
+        assert_eq!(command.serialize()[2], 0xDB);
+
+        let state = MUFrame::elevator_state("#STM:L0:R16:A1:S0:M0:E#\r\n").unwrap();
+        let received = MUFrame::deserialize(&state.serialize()).unwrap();
+        assert_eq!(received.get_opcode(), Opcode::ElevatorState);
+        assert_eq!(received, state);
+
+        // Фреймы без данных: size = 0
+        let status_request = MUFrame::status_request().serialize();
+        assert_eq!(status_request.len(), FRAME_OVERHEAD);
+        assert_eq!(
+            MUFrame::deserialize(&status_request).unwrap().get_opcode(),
+            Opcode::StatusRequest
+        );
+        assert_eq!(
+            MUFrame::deserialize(&MUFrame::ack().serialize()).unwrap(),
+            MUFrame::ack()
+        );
+
+        assert!(MUFrame::console("").is_err());
+    }
+
+    #[test]
+    fn test_binary_payload() {
+        let chunk = vec![0x00, 0xAA, 0xBB, 0xFF, 0x80];
+        let frame = MUFrame::with_opcode(Opcode::Unknown(0xE0), chunk.clone()).unwrap();
+        let received = MUFrame::deserialize(&frame.serialize()).unwrap();
+        assert_eq!(received.get_opcode(), Opcode::Unknown(0xE0));
+        assert_eq!(received.get_data(), chunk);
+
+        // Для текстовых кодов операций ограничение ASCII сохраняется
+        assert!(MUFrame::with_opcode(Opcode::Console, chunk.clone()).is_err());
+        let mut console = MUFrame::new();
+        console.length = chunk.len() as u8;
+        console.data = chunk;
+        console.update_crc();
+        assert_eq!(
+            MUFrame::deserialize(&console.serialize()),
+            Err(FrameError::BadEncoding)
+        );
+
+        assert_eq!(
+            MUFrame::with_opcode(Opcode::Ack, Vec::new()),
+            Err("Data is empty".to_string())
+        );
+        assert_eq!(
+            MUFrame::with_opcode(Opcode::Unknown(0xE0), vec![0; 256]),
+            Err("Data too long: 256 bytes, max 255".to_string())
+        );
+        assert_eq!(
+            MUFrame::new().set_data(Vec::new()),
+            Err(FrameError::EmptyData)
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn prop_deserialize_never_panics(bytes in proptest::collection::vec(any::<u8>(), 0..300)) {
+            let _ = MUFrame::deserialize(&bytes);
+        }
+
+        #[test]
+        fn prop_deserialize_framed_garbage_never_panics(
+            length in any::<u8>(),
+            body in proptest::collection::vec(any::<u8>(), 0..300),
+        ) {
+            let mut bytes = vec![SYNC1, length];
+            bytes.extend(body);
+            let _ = MUFrame::deserialize(&bytes);
+        }
+
+        #[test]
+        fn prop_binary_roundtrip(
+            opcode in any::<u8>().prop_filter("binary opcode", |opcode| !Opcode::from(*opcode).is_text()),
+            data in proptest::collection::vec(any::<u8>(), 1..=255),
+        ) {
+            let frame = MUFrame::with_opcode(Opcode::from(opcode), data).unwrap();
+            prop_assert_eq!(MUFrame::deserialize(&frame.serialize()), Ok(frame));
+        }
+
+        #[test]
+        fn prop_serialize_roundtrip(data in "[ -~]{1,255}") {
+            let mut frame = MUFrame::new();
+            frame.set_data(data.as_bytes().to_vec()).unwrap();
+            prop_assert_eq!(MUFrame::deserialize(&frame.serialize()), Ok(frame));
+        }
+    }
 }
@@ -0,0 +1,83 @@
+use std::fmt::Display;
+
+/// Код операции фрейма протокола "МЮ", определяет тип данных в поле `data`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Opcode {
+    /// 0xC0 - текстовая консоль (запросы `get`/`set` и ответы на них)
+    Console,
+    /// 0xDA - данные о состоянии лифта
+    ElevatorState,
+    /// 0xDB - команда от компьютера к микроконтроллеру
+    Command,
+    /// 0xDC - подтверждение приема
+    Ack,
+    /// 0xDD - запрос состояния
+    StatusRequest,
+    /// Код операции, не описанный в протоколе
+    Unknown(u8),
+}
+
+impl Opcode {
+    /// Признак текстового кода операции: данные фрейма должны состоять из ASCII-символов
+    pub fn is_text(&self) -> bool {
+        matches!(
+            self,
+            Opcode::Console | Opcode::ElevatorState | Opcode::Command
+        )
+    }
+}
+
+impl From<u8> for Opcode {
+    fn from(value: u8) -> Self {
+        match value {
+            0xC0 => Opcode::Console,
+            0xDA => Opcode::ElevatorState,
+            0xDB => Opcode::Command,
+            0xDC => Opcode::Ack,
+            0xDD => Opcode::StatusRequest,
+            other => Opcode::Unknown(other),
+        }
+    }
+}
+
+impl From<Opcode> for u8 {
+    fn from(opcode: Opcode) -> Self {
+        match opcode {
+            Opcode::Console => 0xC0,
+            Opcode::ElevatorState => 0xDA,
+            Opcode::Command => 0xDB,
+            Opcode::Ack => 0xDC,
+            Opcode::StatusRequest => 0xDD,
+            Opcode::Unknown(value) => value,
+        }
+    }
+}
+
+impl Display for Opcode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Opcode::Console => "console",
+            Opcode::ElevatorState => "elevator state",
+            Opcode::Command => "command",
+            Opcode::Ack => "ack",
+            Opcode::StatusRequest => "status request",
+            Opcode::Unknown(_) => "unknown",
+        };
+        write!(f, "{name} ({:#04X})", u8::from(*self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_opcode_conversion() {
+        for value in 0..=u8::MAX {
+            assert_eq!(u8::from(Opcode::from(value)), value);
+        }
+        assert_eq!(Opcode::from(0xDA), Opcode::ElevatorState);
+        assert_eq!(Opcode::from(0x42), Opcode::Unknown(0x42));
+        assert_eq!(Opcode::Ack.to_string(), "ack (0xDC)");
+    }
+}
@@ -0,0 +1,519 @@
+//! # Подписка на телеметрию (состояние лифта)
+//!
+//! > Подписка обслуживается фоновым потоком: он включает выбранный режим стриминга, принимает
+//! > фреймы состояния лифта (0xDA) и передает их подписчику уже разобранными.
+//! > При ошибке связи соединение устанавливается заново, подписка при этом не прерывается.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use log::{debug, warn};
+
+use crate::client::HostClient;
+use crate::dispatcher::ReplyFilter;
+use crate::elevator_state::ElevatorState;
+use crate::mu_frame::MUFrame;
+use crate::opcode::Opcode;
+use crate::transport::Transport;
+
+/// Период по умолчанию для периодического режима и режима по требованию
+const DEFAULT_PERIOD: Duration = Duration::from_secs(1);
+/// Пауза перед повторным подключением по умолчанию
+const DEFAULT_RECONNECT_DELAY: Duration = Duration::from_secs(2);
+/// Максимальное время ожидания фрейма, после которого проверяется запрос на остановку подписки
+const STOP_CHECK_INTERVAL: Duration = Duration::from_millis(100);
+/// Максимальное время ожидания завершения фонового потока при удалении подписки
+const STOP_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Режимы стриминга данных от устройства
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamingMode {
+    /// Без стриминга
+    SilentMode = 0,
+    /// Отправка данных, в случае изменения их состояния (и наличия)
+    OnChangeMode = 1,
+    /// Отправка данных периодически, с заданным в настройках (periodicity) периодом
+    PeriodicMode = 2,
+    /// Отправка данных по требованию (запрос состояния 0xDD)
+    OnDemandMode = 3,
+}
+
+/// Параметры подписки на телеметрию
+#[derive(Debug, Clone, PartialEq)]
+pub struct TelemetryOptions {
+    /// Режим стриминга
+    mode: StreamingMode,
+    /// Период отправки данных платой (`PeriodicMode`) или опроса платы (`OnDemandMode`)
+    period: Duration,
+    /// Пауза перед повторным подключением после ошибки связи
+    reconnect_delay: Duration,
+}
+
+impl TelemetryOptions {
+    pub fn new(mode: StreamingMode) -> Self {
+        TelemetryOptions {
+            mode,
+            period: DEFAULT_PERIOD,
+            reconnect_delay: DEFAULT_RECONNECT_DELAY,
+        }
+    }
+
+    /// Период отправки данных платой (`PeriodicMode`) или опроса платы (`OnDemandMode`)
+    pub fn with_period(mut self, period: Duration) -> Self {
+        self.period = period;
+        self
+    }
+
+    /// Пауза перед повторным подключением после ошибки связи
+    pub fn with_reconnect_delay(mut self, delay: Duration) -> Self {
+        self.reconnect_delay = delay;
+        self
+    }
+
+    pub fn get_mode(&self) -> StreamingMode {
+        self.mode
+    }
+
+    pub fn get_period(&self) -> Duration {
+        self.period
+    }
+}
+
+/// ## Включение режима стриминга `mode`, возвращает отклик платы
+pub fn set_streaming_mode<T: Transport>(
+    client: &mut HostClient<T>,
+    mode: StreamingMode,
+) -> Result<String, String> {
+    // Отклик на предыдущее переключение режима ("mode: N") не принимается за отклик
+    let response = client.send_request_matching(
+        &format!("set mode {}", mode as u8),
+        ReplyFilter::prefix(Opcode::Console, format!("mode: {}", mode as u8).as_bytes()),
+    )?;
+    if !response.contains(&format!("mode: {}", mode as u8)) {
+        return Err(format!(
+            "Invalid response to streaming mode request: {response}"
+        ));
+    }
+    Ok(response)
+}
+
+/// Включение режима стриминга согласно `options`
+/// Для `PeriodicMode` предварительно устанавливается период (`periodicity`, мс)
+fn start_streaming<T: Transport>(
+    client: &mut HostClient<T>,
+    options: &TelemetryOptions,
+) -> Result<(), String> {
+    if options.mode == StreamingMode::PeriodicMode {
+        let period = options.period.as_millis();
+        let response = client.send_request_matching(
+            &format!("set periodicity {period}"),
+            ReplyFilter::prefix(Opcode::Console, b"periodicity:"),
+        )?;
+        if !response.contains("periodicity") {
+            return Err(format!(
+                "Invalid response to periodicity request: {response}"
+            ));
+        }
+    }
+    set_streaming_mode(client, options.mode)?;
+    Ok(())
+}
+
+/// Подписка на телеметрию: итератор состояний лифта
+/// Итерация завершается только после остановки фонового потока; удаление подписки останавливает поток
+pub struct TelemetrySubscription {
+    states: Receiver<ElevatorState>,
+    stop: Arc<AtomicBool>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl TelemetrySubscription {
+    /// Ожидание очередного состояния лифта не дольше `timeout`
+    pub fn recv_timeout(&self, timeout: Duration) -> Option<ElevatorState> {
+        self.states.recv_timeout(timeout).ok()
+    }
+
+    /// Получение состояния лифта без ожидания
+    pub fn try_recv(&self) -> Option<ElevatorState> {
+        self.states.try_recv().ok()
+    }
+}
+
+impl Iterator for TelemetrySubscription {
+    type Item = ElevatorState;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.states.recv().ok()
+    }
+}
+
+impl Drop for TelemetrySubscription {
+    /// Поток, не завершившийся за `STOP_TIMEOUT` (например, во время попыток подключения),
+    /// не ожидается: он завершится сам после проверки запроса на остановку
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        let Some(worker) = self.worker.take() else {
+            return;
+        };
+        let deadline = Instant::now() + STOP_TIMEOUT;
+        while !worker.is_finished() {
+            if Instant::now() >= deadline {
+                warn!("Telemetry: worker is busy, detached");
+                return;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        let _ = worker.join();
+    }
+}
+
+/// ## Подписка на телеметрию
+/// * `connect` - установка соединения с платой; вызывается повторно после ошибок связи
+/// * `options` - режим стриминга и его параметры
+///
+/// ## Пример
+/// ```ignore
+/// let options = TelemetryOptions::new(StreamingMode::OnDemandMode).with_period(Duration::from_millis(500));
+/// for state in subscribe(|| HostClient::connect_url(url, timeout), options) {
+///     println!("{}", state.floor);
+/// }
+/// ```
+pub fn subscribe<T, F>(connect: F, options: TelemetryOptions) -> TelemetrySubscription
+where
+    T: Transport,
+    F: FnMut() -> Result<HostClient<T>, String> + Send + 'static,
+{
+    let (sender, states) = mpsc::channel();
+    let stop = Arc::new(AtomicBool::new(false));
+    let worker_stop = stop.clone();
+    let worker = thread::spawn(move || run_subscription(connect, options, sender, worker_stop));
+
+    TelemetrySubscription {
+        states,
+        stop,
+        worker: Some(worker),
+    }
+}
+
+/// Результат сеанса связи
+enum SessionEnd {
+    /// Подписка остановлена или подписчик удален
+    Stopped,
+    /// Ошибка связи: требуется повторное подключение
+    Failed(String),
+}
+
+/// Фоновый поток подписки: подключение, прием телеметрии, повторное подключение после ошибок
+fn run_subscription<T, F>(
+    mut connect: F,
+    options: TelemetryOptions,
+    sender: Sender<ElevatorState>,
+    stop: Arc<AtomicBool>,
+) where
+    T: Transport,
+    F: FnMut() -> Result<HostClient<T>, String>,
+{
+    while !stop.load(Ordering::Relaxed) {
+        let error = match connect() {
+            Ok(mut client) => match run_session(&mut client, &options, &sender, &stop) {
+                SessionEnd::Stopped => {
+                    // Восстановление режима без стриминга, ошибка не влияет на завершение подписки
+                    if let Err(e) = set_streaming_mode(&mut client, StreamingMode::SilentMode) {
+                        debug!("Telemetry: unable to stop streaming: {e}");
+                    }
+                    return;
+                }
+                SessionEnd::Failed(e) => e,
+            },
+            Err(e) => e,
+        };
+        warn!("Telemetry: {error}, reconnecting");
+        wait(&stop, options.reconnect_delay);
+    }
+}
+
+/// Сеанс связи: включение стриминга и прием телеметрии до ошибки или остановки
+fn run_session<T: Transport>(
+    client: &mut HostClient<T>,
+    options: &TelemetryOptions,
+    sender: &Sender<ElevatorState>,
+    stop: &AtomicBool,
+) -> SessionEnd {
+    if let Err(e) = start_streaming(client, options) {
+        return SessionEnd::Failed(e);
+    }
+
+    while !stop.load(Ordering::Relaxed) {
+        // `polled` - состояние получено опросом: следующий опрос - через период
+        let (frame, polled) = match options.mode {
+            StreamingMode::OnDemandMode => {
+                // Фреймы, принятые без запроса, передаются подписчику до очередного опроса
+                match client.recv_unsolicited(Duration::ZERO) {
+                    Ok(Some(frame)) => (Some(frame), false),
+                    Ok(None) => {
+                        match client.exchange(MUFrame::status_request(), Opcode::ElevatorState) {
+                            Ok(frame) => (Some(frame), true),
+                            Err(e) => return SessionEnd::Failed(e),
+                        }
+                    }
+                    Err(e) => return SessionEnd::Failed(e),
+                }
+            }
+            _ => match client.recv_unsolicited(STOP_CHECK_INTERVAL) {
+                Ok(frame) => (frame, false),
+                Err(e) => return SessionEnd::Failed(e),
+            },
+        };
+
+        // Состояние передается подписчику до ожидания следующего опроса
+        if let Some(state) = frame.and_then(decode_state)
+            && sender.send(state).is_err()
+        {
+            return SessionEnd::Stopped;
+        }
+        if polled {
+            wait(stop, options.period);
+        }
+    }
+    SessionEnd::Stopped
+}
+
+/// Разбор фрейма состояния лифта; остальные фреймы и ошибки разбора пропускаются
+fn decode_state(frame: MUFrame) -> Option<ElevatorState> {
+    if frame.get_opcode() != Opcode::ElevatorState {
+        debug!("Telemetry: frame {} skipped", frame.get_opcode());
+        return None;
+    }
+    let state = String::from_utf8_lossy(frame.get_data());
+    match ElevatorState::parse_lenient(&state) {
+        Ok(state) => Some(state),
+        Err(e) => {
+            warn!("Telemetry: {e}: {state:?}");
+            None
+        }
+    }
+}
+
+/// Ожидание `duration` с проверкой запроса на остановку
+fn wait(stop: &AtomicBool, duration: Duration) {
+    let mut remaining = duration;
+    while !remaining.is_zero() && !stop.load(Ordering::Relaxed) {
+        let step = remaining.min(STOP_CHECK_INTERVAL);
+        thread::sleep(step);
+        remaining -= step;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frame_decoder::MUFrameDecoder;
+    use crate::transport::MemoryTransport;
+    use std::io::{Read, Write};
+    use std::sync::Mutex;
+
+    /// Эмулятор интерфейсной платы: отвечает на консольные запросы и запрос состояния,
+    /// в режиме стриминга 1 передает телеметрию после включения режима, в режиме 2 - с периодом `periodicity`;
+    /// перед откликом на консольный запрос передает запоздавший отклик на предыдущий запрос
+    /// (на первый запрос - отклик из прошлого сеанса); после `limit` запросов разрывает соединение
+    fn run_board(
+        mut board: MemoryTransport,
+        floor: i32,
+        limit: usize,
+        requests: Arc<Mutex<Vec<String>>>,
+    ) {
+        let mut decoder = MUFrameDecoder::new();
+        let mut buffer = [0; 256];
+        let state = |floor: i32| {
+            let state = ElevatorState {
+                floor,
+                ..ElevatorState::default()
+            };
+            MUFrame::elevator_state(&state.to_wire_string()).unwrap()
+        };
+        let mut stale_reply = MUFrame::console("groupnumber: 2\r\n").unwrap();
+        let mut periodicity = DEFAULT_PERIOD;
+        // Период передачи телеметрии в режиме стриминга 2 (`None` - другой режим)
+        let mut period = None;
+        let mut next_state = Instant::now();
+        for _ in 0..limit {
+            let frame = loop {
+                if let Some(frame) = decoder.next_frame() {
+                    break frame;
+                }
+                match board.read(&mut buffer) {
+                    Ok(0) => return,
+                    Ok(received) => decoder.push(&buffer[..received]),
+                    Err(_) => {}
+                }
+                if let Some(period) = period
+                    && Instant::now() >= next_state
+                {
+                    if board.write_all(&state(floor).serialize()).is_err() {
+                        return;
+                    }
+                    next_state = Instant::now() + period;
+                }
+            };
+            {
+                let request = String::from_utf8_lossy(frame.get_data()).trim().to_string();
+                requests.lock().unwrap().push(request.clone());
+                let reply = match (frame.get_opcode(), request.as_str()) {
+                    (Opcode::StatusRequest, _) => state(floor),
+                    (_, "hello") => MUFrame::console("Hi!\r\n").unwrap(),
+                    (_, request) => {
+                        let (key, value) =
+                            request.trim_start_matches("set ").split_once(' ').unwrap();
+                        MUFrame::console(&format!("{key}: {value}")).unwrap()
+                    }
+                };
+                if reply.get_opcode() == Opcode::Console {
+                    board.write_all(&stale_reply.serialize()).unwrap();
+                    stale_reply = reply.clone();
+                }
+                board.write_all(&reply.serialize()).unwrap();
+                if request == "set mode 1" {
+                    board.write_all(&state(floor).serialize()).unwrap();
+                }
+                if let Some(value) = request.strip_prefix("set periodicity ") {
+                    periodicity = Duration::from_millis(value.parse().unwrap());
+                }
+                if request.starts_with("set mode ") {
+                    period = (request == "set mode 2").then_some(periodicity);
+                    board
+                        .set_read_timeout(periodicity.min(Duration::from_secs(1)))
+                        .unwrap();
+                }
+            }
+        }
+    }
+
+    /// Подключение к эмулятору платы; каждое подключение - новый этаж
+    /// * `first_limit` - количество запросов, после которых первое соединение разрывается
+    fn create_connector(
+        requests: Arc<Mutex<Vec<String>>>,
+        first_limit: usize,
+    ) -> impl FnMut() -> Result<HostClient<MemoryTransport>, String> + Send + 'static {
+        let mut floor = 0;
+        move || {
+            floor += 1;
+            let limit = if floor == 1 { first_limit } else { usize::MAX };
+            let (host, board) = MemoryTransport::pair();
+            let requests = requests.clone();
+            thread::spawn(move || run_board(board, floor, limit, requests));
+            HostClient::new(host, Duration::from_secs(1))
+        }
+    }
+
+    #[test]
+    fn test_on_change_subscription() {
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let mut subscription = subscribe(
+            create_connector(requests.clone(), usize::MAX),
+            TelemetryOptions::new(StreamingMode::OnChangeMode),
+        );
+
+        assert_eq!(subscription.next().unwrap().floor, 1);
+        drop(subscription);
+        assert!(requests.lock().unwrap().contains(&"set mode 0".to_string()));
+    }
+
+    #[test]
+    fn test_on_demand_and_periodic_subscription() {
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let subscription = subscribe(
+            create_connector(requests.clone(), usize::MAX),
+            TelemetryOptions::new(StreamingMode::OnDemandMode).with_period(Duration::ZERO),
+        );
+        let states: Vec<ElevatorState> = subscription.take(3).collect();
+        assert_eq!(states.len(), 3);
+        assert!(requests.lock().unwrap().contains(&"set mode 3".to_string()));
+
+        // Опрошенное состояние передается сразу, а не через период опроса
+        let subscription = subscribe(
+            create_connector(Arc::new(Mutex::new(Vec::new())), usize::MAX),
+            TelemetryOptions::new(StreamingMode::OnDemandMode).with_period(Duration::from_secs(5)),
+        );
+        assert!(subscription.recv_timeout(Duration::from_secs(1)).is_some());
+        drop(subscription);
+
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let subscription = subscribe(
+            create_connector(requests.clone(), usize::MAX),
+            TelemetryOptions::new(StreamingMode::PeriodicMode)
+                .with_period(Duration::from_millis(50)),
+        );
+        // Плата передает состояние без запросов с заданным периодом
+        for _ in 0..3 {
+            let state = subscription.recv_timeout(Duration::from_secs(1)).unwrap();
+            assert_eq!(state.floor, 1);
+        }
+        drop(subscription);
+        let requests = requests.lock().unwrap();
+        assert!(requests.contains(&"set periodicity 50".to_string()));
+        assert!(requests.contains(&"set mode 2".to_string()));
+        // Состояние не запрашивалось (данные запроса состояния пусты)
+        assert!(!requests.iter().any(|request| request.is_empty()));
+    }
+
+    #[test]
+    fn test_drop_during_connect() {
+        // Подключение к неотвечающей плате: попытки handshake занимают больше STOP_TIMEOUT
+        let subscription = subscribe(
+            || {
+                thread::sleep(STOP_TIMEOUT * 3);
+                Err::<HostClient<MemoryTransport>, String>("Handshake failed!".to_string())
+            },
+            TelemetryOptions::new(StreamingMode::OnChangeMode),
+        );
+        thread::sleep(Duration::from_millis(50));
+
+        let started = Instant::now();
+        drop(subscription);
+        assert!(started.elapsed() < STOP_TIMEOUT * 2);
+    }
+
+    #[test]
+    fn test_stale_replies() {
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let mut client = create_connector(requests.clone(), usize::MAX)().unwrap();
+
+        // Отклик из прошлого сеанса не прерывает handshake
+        assert_eq!(*requests.lock().unwrap(), vec!["hello"]);
+        // Перед каждым откликом плата передает отклик на предыдущий запрос
+        assert_eq!(
+            set_streaming_mode(&mut client, StreamingMode::OnChangeMode).unwrap(),
+            "mode: 1"
+        );
+        assert_eq!(
+            set_streaming_mode(&mut client, StreamingMode::SilentMode).unwrap(),
+            "mode: 0"
+        );
+    }
+
+    #[test]
+    fn test_reconnect() {
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        // Первое соединение разрывается после hello, set mode 3 и одного запроса состояния
+        let mut connect = create_connector(requests, 3);
+        let mut attempts = 0;
+        let subscription = subscribe(
+            move || {
+                attempts += 1;
+                match attempts {
+                    2 => Err("Board is not connected".to_string()),
+                    _ => connect(),
+                }
+            },
+            TelemetryOptions::new(StreamingMode::OnDemandMode)
+                .with_period(Duration::ZERO)
+                .with_reconnect_delay(Duration::ZERO),
+        );
+
+        let floors: Vec<i32> = subscription.take(2).map(|state| state.floor).collect();
+        assert_eq!(floors, vec![1, 2]);
+    }
+}
@@ -0,0 +1,429 @@
+//! # Транспорт для обмена фреймами с интерфейсной платой
+//!
+//! > `HostClient` работает с любым двунаправленным потоком байтов, реализующим `Transport`:
+//! > последовательным портом, TCP-сокетом (ser2net, стендовый мост), PTY или каналом в памяти.
+//! > Транспорт выбирается по URL: `serial:///dev/ttyS4?baud=115200`, `tcp://host:port`, `pty:///dev/pts/3`
+
+use std::collections::VecDeque;
+use std::io::{self, ErrorKind, Read, Write};
+use std::net::TcpStream;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+/// Скорость последовательного порта, если в URL не указан параметр `baud`
+const DEFAULT_BAUDRATE: u32 = 115200;
+
+/// Двунаправленный поток байтов между host и интерфейсной платой
+pub trait Transport: Read + Write + Send {
+    /// Установка максимального времени ожидания данных одним вызовом `read`
+    /// По истечении времени `read` возвращает ошибку `TimedOut` или `WouldBlock`
+    fn set_read_timeout(&mut self, timeout: Duration) -> io::Result<()>;
+}
+
+impl<T: Transport + ?Sized> Transport for Box<T> {
+    fn set_read_timeout(&mut self, timeout: Duration) -> io::Result<()> {
+        (**self).set_read_timeout(timeout)
+    }
+}
+
+/// Последовательный порт (UART)
+pub struct SerialTransport {
+    port: Box<dyn serialport::SerialPort>,
+}
+
+impl SerialTransport {
+    /// Открытие последовательного порта `port_name` со скоростью `baudrate`
+    pub fn open(port_name: &str, baudrate: u32) -> Result<Self, String> {
+        let port = serialport::new(port_name, baudrate)
+            .open()
+            .map_err(|e| format!("Unable to open serial port {port_name}: {e}"))?;
+        Ok(SerialTransport { port })
+    }
+}
+
+impl Read for SerialTransport {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.port.read(buf)
+    }
+}
+
+impl Write for SerialTransport {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.port.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.port.flush()
+    }
+}
+
+impl Transport for SerialTransport {
+    fn set_read_timeout(&mut self, timeout: Duration) -> io::Result<()> {
+        self.port.set_timeout(timeout).map_err(io::Error::from)
+    }
+}
+
+/// TCP-соединение (ser2net, стендовый мост UART - Ethernet)
+pub struct TcpTransport {
+    stream: TcpStream,
+}
+
+impl TcpTransport {
+    /// Подключение к `address` вида `host:port`
+    pub fn connect(address: &str) -> Result<Self, String> {
+        let stream = TcpStream::connect(address)
+            .map_err(|e| format!("Unable to connect to {address}: {e}"))?;
+        stream.set_nodelay(true).map_err(|e| e.to_string())?;
+        Ok(TcpTransport { stream })
+    }
+}
+
+impl Read for TcpTransport {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.stream.read(buf)
+    }
+}
+
+impl Write for TcpTransport {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.stream.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.stream.flush()
+    }
+}
+
+impl Transport for TcpTransport {
+    fn set_read_timeout(&mut self, timeout: Duration) -> io::Result<()> {
+        self.stream.set_read_timeout(Some(timeout))
+    }
+}
+
+/// Псевдотерминал (например, созданный socat или эмулятором платы)
+#[cfg(unix)]
+pub struct PtyTransport {
+    port: serialport::TTYPort,
+}
+
+#[cfg(unix)]
+impl PtyTransport {
+    /// Открытие псевдотерминала `path` (например, `/dev/pts/3`)
+    pub fn open(path: &str) -> Result<Self, String> {
+        let port = serialport::new(path, DEFAULT_BAUDRATE)
+            .open_native()
+            .map_err(|e| format!("Unable to open PTY {path}: {e}"))?;
+        Ok(PtyTransport { port })
+    }
+
+    /// Создание пары связанных псевдотерминалов (master, slave) для эмуляции платы
+    pub fn pair() -> Result<(Self, Self), String> {
+        let (master, slave) = serialport::TTYPort::pair().map_err(|e| e.to_string())?;
+        Ok((PtyTransport { port: master }, PtyTransport { port: slave }))
+    }
+
+    /// Путь к псевдотерминалу
+    pub fn get_name(&self) -> Option<String> {
+        serialport::SerialPort::name(&self.port)
+    }
+}
+
+#[cfg(unix)]
+impl Read for PtyTransport {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.port.read(buf)
+    }
+}
+
+#[cfg(unix)]
+impl Write for PtyTransport {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.port.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.port.flush()
+    }
+}
+
+#[cfg(unix)]
+impl Transport for PtyTransport {
+    fn set_read_timeout(&mut self, timeout: Duration) -> io::Result<()> {
+        serialport::SerialPort::set_timeout(&mut self.port, timeout).map_err(io::Error::from)
+    }
+}
+
+/// Однонаправленный канал в памяти
+#[derive(Debug, Default)]
+struct Pipe {
+    state: Mutex<PipeState>,
+    ready: Condvar,
+}
+
+#[derive(Debug, Default)]
+struct PipeState {
+    bytes: VecDeque<u8>,
+    /// Одна из сторон канала закрыта
+    closed: bool,
+}
+
+impl Pipe {
+    fn lock(&self) -> MutexGuard<'_, PipeState> {
+        match self.state.lock() {
+            Ok(state) => state,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    fn close(&self) {
+        self.lock().closed = true;
+        self.ready.notify_all();
+    }
+}
+
+/// Дуплексный канал в памяти: для тестов и эмуляторов платы без оборудования
+#[derive(Debug)]
+pub struct MemoryTransport {
+    incoming: Arc<Pipe>,
+    outgoing: Arc<Pipe>,
+    read_timeout: Duration,
+}
+
+impl MemoryTransport {
+    /// Создание пары связанных концов канала: записанное в один конец читается из другого
+    pub fn pair() -> (Self, Self) {
+        let forward = Arc::new(Pipe::default());
+        let backward = Arc::new(Pipe::default());
+        let read_timeout = Duration::from_secs(1);
+        (
+            MemoryTransport {
+                incoming: backward.clone(),
+                outgoing: forward.clone(),
+                read_timeout,
+            },
+            MemoryTransport {
+                incoming: forward,
+                outgoing: backward,
+                read_timeout,
+            },
+        )
+    }
+}
+
+impl Read for MemoryTransport {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let deadline = Instant::now() + self.read_timeout;
+        let mut state = self.incoming.lock();
+        while state.bytes.is_empty() && !state.closed {
+            let Some(remaining) = deadline.checked_duration_since(Instant::now()) else {
+                return Err(ErrorKind::TimedOut.into());
+            };
+            state = match self.incoming.ready.wait_timeout(state, remaining) {
+                Ok((state, _)) => state,
+                Err(poisoned) => poisoned.into_inner().0,
+            };
+        }
+
+        let count = buf.len().min(state.bytes.len());
+        for (target, byte) in buf.iter_mut().zip(state.bytes.drain(..count)) {
+            *target = byte;
+        }
+        Ok(count)
+    }
+}
+
+impl Write for MemoryTransport {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut state = self.outgoing.lock();
+        if state.closed {
+            return Err(ErrorKind::BrokenPipe.into());
+        }
+        state.bytes.extend(buf);
+        drop(state);
+        self.outgoing.ready.notify_all();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Transport for MemoryTransport {
+    fn set_read_timeout(&mut self, timeout: Duration) -> io::Result<()> {
+        self.read_timeout = timeout;
+        Ok(())
+    }
+}
+
+impl Drop for MemoryTransport {
+    fn drop(&mut self) {
+        self.incoming.close();
+        self.outgoing.close();
+    }
+}
+
+/// Транспорт, заданный URL
+#[derive(Debug, Clone, PartialEq)]
+enum TransportUrl {
+    Serial { path: String, baudrate: u32 },
+    Tcp { address: String },
+    Pty { path: String },
+}
+
+/// Разбор URL транспорта
+fn parse_transport_url(url: &str) -> Result<TransportUrl, String> {
+    let Some((scheme, rest)) = url.split_once("://") else {
+        return Err(format!(
+            "Bad transport URL \"{url}\": expected serial://, tcp:// or pty://"
+        ));
+    };
+    let (target, query) = rest.split_once('?').unwrap_or((rest, ""));
+    if target.is_empty() {
+        return Err(format!("Bad transport URL \"{url}\": missing target"));
+    }
+
+    let mut baudrate = DEFAULT_BAUDRATE;
+    for parameter in query.split('&').filter(|parameter| !parameter.is_empty()) {
+        match parameter.split_once('=') {
+            Some(("baud", value)) if scheme == "serial" => {
+                baudrate = value
+                    .parse()
+                    .map_err(|_| format!("Bad baudrate \"{value}\" in transport URL \"{url}\""))?;
+            }
+            _ => {
+                return Err(format!(
+                    "Unknown parameter \"{parameter}\" in transport URL \"{url}\""
+                ));
+            }
+        }
+    }
+
+    match scheme {
+        "serial" => Ok(TransportUrl::Serial {
+            path: target.to_string(),
+            baudrate,
+        }),
+        "tcp" if target.contains(':') => Ok(TransportUrl::Tcp {
+            address: target.to_string(),
+        }),
+        "tcp" => Err(format!(
+            "Bad transport URL \"{url}\": expected tcp://host:port"
+        )),
+        "pty" => Ok(TransportUrl::Pty {
+            path: target.to_string(),
+        }),
+        _ => Err(format!("Unknown transport \"{scheme}\" in URL \"{url}\"")),
+    }
+}
+
+/// ## Открытие транспорта по URL
+/// * `serial:///dev/ttyS4?baud=115200` - последовательный порт (скорость по умолчанию 115200)
+/// * `tcp://host:port` - TCP-соединение
+/// * `pty:///dev/pts/3` - псевдотерминал
+pub fn open_transport(url: &str) -> Result<Box<dyn Transport>, String> {
+    match parse_transport_url(url)? {
+        TransportUrl::Serial { path, baudrate } => {
+            Ok(Box::new(SerialTransport::open(&path, baudrate)?))
+        }
+        TransportUrl::Tcp { address } => Ok(Box::new(TcpTransport::connect(&address)?)),
+        #[cfg(unix)]
+        TransportUrl::Pty { path } => Ok(Box::new(PtyTransport::open(&path)?)),
+        #[cfg(not(unix))]
+        TransportUrl::Pty { .. } => Err("PTY transport is not supported".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_transport_url() {
+        assert_eq!(
+            parse_transport_url("serial:///dev/ttyS4?baud=9600"),
+            Ok(TransportUrl::Serial {
+                path: "/dev/ttyS4".to_string(),
+                baudrate: 9600
+            })
+        );
+        assert_eq!(
+            parse_transport_url("serial:///dev/ttyS4"),
+            Ok(TransportUrl::Serial {
+                path: "/dev/ttyS4".to_string(),
+                baudrate: DEFAULT_BAUDRATE
+            })
+        );
+        assert_eq!(
+            parse_transport_url("tcp://192.168.0.10:4001"),
+            Ok(TransportUrl::Tcp {
+                address: "192.168.0.10:4001".to_string()
+            })
+        );
+        assert_eq!(
+            parse_transport_url("pty:///dev/pts/3"),
+            Ok(TransportUrl::Pty {
+                path: "/dev/pts/3".to_string()
+            })
+        );
+
+        assert!(parse_transport_url("/dev/ttyS4").is_err());
+        assert!(parse_transport_url("tcp://localhost").is_err());
+        assert!(parse_transport_url("serial:///dev/ttyS4?baud=fast").is_err());
+        assert!(parse_transport_url("tcp://localhost:4001?baud=9600").is_err());
+        assert!(parse_transport_url("udp://localhost:4001").is_err());
+    }
+
+    #[test]
+    fn test_memory_transport() {
+        let (mut host, mut board) = MemoryTransport::pair();
+        host.set_read_timeout(Duration::from_millis(10)).unwrap();
+
+        let mut buf = [0; 8];
+        assert_eq!(host.read(&mut buf).unwrap_err().kind(), ErrorKind::TimedOut);
+
+        board.write_all(b"Hi!\r\n").unwrap();
+        assert_eq!(host.read(&mut buf).unwrap(), 5);
+        assert_eq!(&buf[..5], b"Hi!\r\n");
+
+        drop(board);
+        assert_eq!(host.read(&mut buf).unwrap(), 0);
+        assert_eq!(
+            host.write(b"hello").unwrap_err().kind(),
+            ErrorKind::BrokenPipe
+        );
+    }
+
+    #[test]
+    fn test_tcp_transport() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("tcp://{}", listener.local_addr().unwrap());
+
+        let mut host = open_transport(&url).unwrap();
+        let (mut board, _) = listener.accept().unwrap();
+
+        host.write_all(b"hello\n").unwrap();
+        let mut buf = [0; 6];
+        board.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"hello\n");
+
+        host.set_read_timeout(Duration::from_millis(10)).unwrap();
+        assert!(matches!(
+            host.read(&mut buf).unwrap_err().kind(),
+            ErrorKind::TimedOut | ErrorKind::WouldBlock
+        ));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_pty_transport() {
+        let (mut board, mut host) = PtyTransport::pair().unwrap();
+        assert!(host.get_name().is_some());
+        board.set_read_timeout(Duration::from_millis(500)).unwrap();
+
+        host.write_all(b"hello\n").unwrap();
+        let mut buf = [0; 6];
+        board.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"hello\n");
+    }
+}
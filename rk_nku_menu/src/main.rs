@@ -1,3 +1,4 @@
+use config_lib::audit_log::{AuditLog, AuditTool};
 use gpiocdev::Request;
 use log::debug;
 use menu_tui::menu_navigation::NavigationManager;
@@ -6,6 +7,8 @@ use std::time::Duration;
 /// ### Путь к файлу-схемы параметров устройства
 const NKU_DEVICE_CONFIG_PATH: &str = "rk_smart_configs/smart_scheme.toml";
 const TUI_APP_CONFIG_PATH: &str = "rk_smart_configs/menu_style.toml";
+/// ### Путь к журналу изменений параметров
+const AUDIT_LOG_PATH: &str = "rk_smart_configs/audit_log.jsonl";
 
 /// Максимальное время бездействия [c], после достижения которого происходит выход из меню
 const IDLE_TIMEOUT_SEC: u64 = 30;
@@ -29,17 +32,25 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     menu.quit();
 
-    let device_config = menu.get_schema_config()?;
+    if menu.is_changes_discarded() {
+        debug!("Выход без сохранения изменений");
+        return Ok(());
+    }
+
+    let device_config = menu.get_shared_config();
+    device_config.update(|config| {
+        config.set_audit_log(AuditLog::open(AUDIT_LOG_PATH), AuditTool::Menu);
+        Ok(())
+    })?;
 
     debug!("Итоговая конфигурация:");
-    for parameter in device_config.get_parameters_names()? {
-        let value = device_config.get_parameter_value(&parameter).unwrap();
+    for (parameter, value) in device_config.snapshot().get_parameters_values() {
         debug!("{}: {}", parameter, value);
     }
 
-    device_config.save_parameters_values()?;
+    device_config.save()?;
 
-    return Ok(());
+    Ok(())
 }
 
 /// ligpiod номер порта кнопки ввода
@@ -73,15 +84,14 @@ fn gpio_navigation_handlers(nav_manager: NavigationManager) {
         debug!("down_irq_thread launched!");
 
         loop {
-            if down_btn_req.has_edge_event().unwrap() {
-                if let Ok(edge) = down_btn_req.read_edge_event() {
-                    if edge.kind == gpiocdev::line::EdgeKind::Rising {
-                        debug!("DownArrow pressed");
-                        // Симуляция нажатия кнопки DOWN
-                        nav_down.navigate_down();
-                        std::thread::sleep(Duration::from_millis(400));
-                    }
-                }
+            if down_btn_req.has_edge_event().unwrap()
+                && let Ok(edge) = down_btn_req.read_edge_event()
+                && edge.kind == gpiocdev::line::EdgeKind::Rising
+            {
+                debug!("DownArrow pressed");
+                // Симуляция нажатия кнопки DOWN
+                nav_down.navigate_down();
+                std::thread::sleep(Duration::from_millis(400));
             }
         }
     });
@@ -98,15 +108,14 @@ fn gpio_navigation_handlers(nav_manager: NavigationManager) {
             .unwrap();
         debug!("return_irq_thread launched!");
         loop {
-            if return_btn_req.has_edge_event().unwrap() {
-                if let Ok(edge) = return_btn_req.read_edge_event() {
-                    if edge.kind == gpiocdev::line::EdgeKind::Rising {
-                        debug!("Return pressed");
-                        // Симуляция нажатия кнопки ENTER
-                        nav_select.select_item();
-                        std::thread::sleep(Duration::from_millis(400));
-                    }
-                }
+            if return_btn_req.has_edge_event().unwrap()
+                && let Ok(edge) = return_btn_req.read_edge_event()
+                && edge.kind == gpiocdev::line::EdgeKind::Rising
+            {
+                debug!("Return pressed");
+                // Симуляция нажатия кнопки ENTER
+                nav_select.select_item();
+                std::thread::sleep(Duration::from_millis(400));
             }
         }
     });
@@ -1,10 +1,18 @@
 mod nku_client;
 mod serial_config;
 
+use std::io::Write;
 use std::str::FromStr;
+use std::time::Duration;
 
 use clap::Parser;
+use config_lib::audit_log::AuditLog;
+use config_lib::merge::{ConflictResolution, MergeConflict};
+use config_lib::scheme_refresh::diff_schemes;
+use config_lib::unit_store::UnitStore;
 use log::{error, warn};
+use nku_client::StreamingMode;
+use protocol_lib::telemetry::TelemetryOptions;
 
 /// Количество попыток выполнить запрос
 const REQUEST_ATTEMPTS: u8 = 5;
@@ -12,9 +20,42 @@ const REQUEST_ATTEMPTS: u8 = 5;
 #[derive(Parser)]
 #[command(author = "Akimov Dmitry MU LLC", name = "nku_sync", version = "0.1.0", about, long_about = None)]
 struct Args {
-    /// Тип команды: pull - запрос сохраненных в устройстве настроек, push - отправка новых настроек
+    /// Тип команды: pull - запрос сохраненных в устройстве настроек, push - отправка новых настроек,
+    /// scheme - построение (обновление) схемы индикатора по описанию параметров от платы,
+    /// monitor - вывод телеметрии (состояния лифта)
     #[arg(short = 'm', long = "mode")]
     mode: CommandMode,
+    /// Переопределение значения параметра схемы без его сохранения: key=value (например, baudrate=9600)
+    #[arg(short = 'o', long = "override")]
+    overrides: Vec<String>,
+    /// Каталог архива настроек устройств: после pull настройки сохраняются в файл устройства --unit
+    #[arg(long = "archive", requires = "unit")]
+    archive: Option<String>,
+    /// Идентификатор устройства в архиве (например, серийный номер платы с шильдика).
+    /// Задается вручную: интерфейсная плата не сообщает свой серийный номер (handshake - только "Hi!")
+    #[arg(long = "unit", requires = "archive")]
+    unit: Option<String>,
+    /// Журнал изменений: сохраненные после pull изменения значений записываются в него
+    #[arg(long = "audit-log")]
+    audit_log: Option<String>,
+    /// Разрешение конфликтов при pull (параметр изменен и локально, и на устройстве):
+    /// device - значение устройства, local - локальное значение, ask - запрос у пользователя;
+    /// если способ не задан, pull с конфликтами завершается ошибкой со списком конфликтов
+    #[arg(long = "on-conflict")]
+    on_conflict: Option<ConflictMode>,
+    /// Транспорт для связи с платой: serial:///dev/ttyS4?baud=115200, tcp://host:port или pty:///dev/pts/3;
+    /// по умолчанию - последовательный порт из схемы rk3399_scheme.toml
+    #[arg(short = 'u', long = "url")]
+    url: Option<String>,
+    /// Режим стриминга телеметрии (monitor): silent, on-change, periodic или on-demand
+    #[arg(long = "streaming", default_value = "on-change", value_parser = parse_streaming_mode)]
+    streaming: StreamingMode,
+    /// Период отправки телеметрии платой (periodic) или опроса платы (on-demand), мс
+    #[arg(long = "period-ms", default_value_t = 1000)]
+    period_ms: u64,
+    /// Запись обновленной схемы (scheme) без подтверждения
+    #[arg(short = 'y', long = "yes")]
+    yes: bool,
 }
 
 fn main() -> Result<(), String> {
@@ -23,11 +64,33 @@ fn main() -> Result<(), String> {
     env_logger::init();
     warn!("rk_nku_sync> command mode: {:?}", args.mode);
 
-    let mut nku_client = nku_client::NkuClient::new()?;
+    if let CommandMode::Scheme = args.mode {
+        return refresh_scheme(&args.overrides, args.url.as_deref(), args.yes);
+    }
+    if let CommandMode::Monitor = args.mode {
+        let options = TelemetryOptions::new(args.streaming)
+            .with_period(Duration::from_millis(args.period_ms));
+        return monitor_telemetry(&args.overrides, args.url.as_deref(), options);
+    }
+
+    let mut nku_client = nku_client::NkuClient::new(&args.overrides, args.url.as_deref())?;
+    if let Some(path) = &args.audit_log {
+        nku_client.set_audit_log(AuditLog::open(path));
+    }
 
     match args.mode {
-        CommandMode::Pull => pull_parameters(&mut nku_client)?,
+        CommandMode::Pull => {
+            pull_parameters(&mut nku_client, args.on_conflict)?;
+            if let (Some(archive), Some(unit)) = (&args.archive, &args.unit) {
+                let store = UnitStore::open(archive)?;
+                store.save_unit(unit, nku_client.get_device_config())?;
+                warn!("rk_nku_sync> parameters of unit {unit} archived to {archive}");
+            }
+        }
         CommandMode::Push => push_parameters(&mut nku_client)?,
+        CommandMode::Scheme | CommandMode::Monitor => {
+            unreachable!("scheme and monitor modes are handled without scheme loading")
+        }
     }
 
     Ok(())
@@ -65,7 +128,7 @@ fn push_parameters(client: &mut nku_client::NkuClient) -> Result<(), String> {
     'start_streaming_loop: loop {
         warn!("Start streaming attempt: {attempts}");
 
-        let result = client.start_elevator_data_streaming(nku_client::StreamingMode::OnChangeMode);
+        let result = client.start_elevator_data_streaming(StreamingMode::OnChangeMode);
 
         if result.is_ok() {
             break 'start_streaming_loop;
@@ -85,19 +148,22 @@ fn push_parameters(client: &mut nku_client::NkuClient) -> Result<(), String> {
 }
 
 /// Получение настроек, сохраненных в устройстве
-fn pull_parameters(client: &mut nku_client::NkuClient) -> Result<(), String> {
+fn pull_parameters(
+    client: &mut nku_client::NkuClient,
+    on_conflict: Option<ConflictMode>,
+) -> Result<(), String> {
     let mut attempts: u8 = 1;
     // Цикл попыток установить соединение
-    'pull_request_loop: loop {
+    let mut merge = 'pull_request_loop: loop {
         warn!("Pull request attempt: {attempts}");
-   
+
         let result = client.pull_parameters_from_device();
-        if result.is_ok() {
-            break 'pull_request_loop;
+        if let Ok(merge) = result {
+            break 'pull_request_loop merge;
         }
         error!(
             "{}",
-            format!("Failed to push parameters: {result:?}").as_str()
+            format!("Failed to pull parameters: {result:?}").as_str()
         );
 
         attempts += 1;
@@ -105,15 +171,133 @@ fn pull_parameters(client: &mut nku_client::NkuClient) -> Result<(), String> {
         if attempts > REQUEST_ATTEMPTS {
             return Err("Pull request failed!".to_string());
         }
+    };
+
+    if on_conflict.is_none() && !merge.conflicts.is_empty() {
+        let conflicts = merge
+            .conflicts
+            .iter()
+            .map(|conflict| {
+                format!(
+                    "{}: local = {}, device = {}",
+                    conflict.key, conflict.local, conflict.device
+                )
+            })
+            .collect::<Vec<String>>()
+            .join("; ");
+        return Err(format!(
+            "Pull conflicts, choose --on-conflict local|device|ask: {conflicts}"
+        ));
+    }
+
+    for conflict in merge.conflicts.clone() {
+        let resolution = match on_conflict {
+            Some(ConflictMode::Local) => ConflictResolution::Local,
+            Some(ConflictMode::Device) => ConflictResolution::Device,
+            Some(ConflictMode::Ask) | None => ask_conflict_resolution(&conflict)?,
+        };
+        warn!(
+            "rk_nku_sync> conflict {}: {} kept",
+            conflict.key,
+            conflict.resolve(resolution)
+        );
+        merge.resolve_conflict(&conflict.key, resolution)?;
+    }
+
+    for key in merge.from_device.iter() {
+        warn!("rk_nku_sync> {key} taken from device");
+    }
+    for key in merge.from_local.iter() {
+        warn!("rk_nku_sync> {key} local change kept");
+    }
+
+    client.apply_merge(&merge)
+}
+
+/// Запрос у пользователя способа разрешения конфликта (по умолчанию - значение устройства)
+fn ask_conflict_resolution(conflict: &MergeConflict) -> Result<ConflictResolution, String> {
+    println!(
+        "Conflict {}: last synced = {}, local = {}, device = {}",
+        conflict.key,
+        conflict.base.as_deref().unwrap_or("-"),
+        conflict.local,
+        conflict.device
+    );
+    print!("Keep [l]ocal or take [d]evice value? [d] ");
+    std::io::stdout().flush().map_err(|e| e.to_string())?;
+
+    let mut answer = String::new();
+    std::io::stdin()
+        .read_line(&mut answer)
+        .map_err(|e| e.to_string())?;
+    match answer.trim() {
+        "l" | "L" => Ok(ConflictResolution::Local),
+        _ => Ok(ConflictResolution::Device),
+    }
+}
+
+/// Построение (обновление) схемы индикатора по описанию параметров от платы
+/// Перед записью выводятся изменения схемы и запрашивается подтверждение (если не указан `assume_yes`)
+fn refresh_scheme(overrides: &[String], url: Option<&str>, assume_yes: bool) -> Result<(), String> {
+    let path_to_scheme = nku_client::NKU_DEVICE_CONFIG_PATH;
+    let (current_scheme, refreshed_scheme) =
+        nku_client::NkuClient::describe_board_scheme(overrides, url, path_to_scheme)?;
+
+    let diff = diff_schemes(&current_scheme, &refreshed_scheme);
+    if diff.is_empty() {
+        println!("Scheme {path_to_scheme} is up to date");
+        return Ok(());
+    }
+    println!("Changes of {path_to_scheme}:\n{diff}");
+
+    if !assume_yes {
+        print!("Write changes? [y/N] ");
+        std::io::stdout().flush().map_err(|e| e.to_string())?;
+        let mut answer = String::new();
+        std::io::stdin()
+            .read_line(&mut answer)
+            .map_err(|e| e.to_string())?;
+        if !answer.trim().eq_ignore_ascii_case("y") {
+            warn!("rk_nku_sync> scheme is not changed");
+            return Ok(());
+        }
     }
 
+    std::fs::write(path_to_scheme, refreshed_scheme).map_err(|e| e.to_string())?;
+    warn!("rk_nku_sync> scheme {path_to_scheme} updated");
     Ok(())
 }
 
+/// Вывод телеметрии до завершения программы; при обрыве связи подключение выполняется заново
+fn monitor_telemetry(
+    overrides: &[String],
+    url: Option<&str>,
+    options: TelemetryOptions,
+) -> Result<(), String> {
+    let subscription = nku_client::NkuClient::subscribe_telemetry(overrides, url, options)?;
+    for state in subscription {
+        println!("{}", state.to_wire_string().trim_end());
+    }
+    Ok(())
+}
+
+/// Разбор режима стриминга телеметрии
+fn parse_streaming_mode(mode: &str) -> Result<StreamingMode, String> {
+    match mode {
+        "silent" => Ok(StreamingMode::SilentMode),
+        "on-change" => Ok(StreamingMode::OnChangeMode),
+        "periodic" => Ok(StreamingMode::PeriodicMode),
+        "on-demand" => Ok(StreamingMode::OnDemandMode),
+        _ => Err(format!("Unknown streaming mode: {mode}")),
+    }
+}
+
 #[derive(Clone, Debug)]
 enum CommandMode {
     Pull,
     Push,
+    Scheme,
+    Monitor,
 }
 
 impl FromStr for CommandMode {
@@ -122,7 +306,28 @@ impl FromStr for CommandMode {
         match s {
             "pull" => Ok(CommandMode::Pull),
             "push" => Ok(CommandMode::Push),
+            "scheme" => Ok(CommandMode::Scheme),
+            "monitor" => Ok(CommandMode::Monitor),
             _ => Err(format!("Unknown command mode: {s}")),
         }
     }
 }
+
+#[derive(Clone, Copy, Debug)]
+enum ConflictMode {
+    Local,
+    Device,
+    Ask,
+}
+
+impl FromStr for ConflictMode {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "local" => Ok(ConflictMode::Local),
+            "device" => Ok(ConflictMode::Device),
+            "ask" => Ok(ConflictMode::Ask),
+            _ => Err(format!("Unknown conflict mode: {s}")),
+        }
+    }
+}
@@ -1,24 +1,28 @@
 use crate::serial_config::SerialPortConfig;
-use config_lib::device_config::DeviceConfig;
-use log::{debug, info};
+use config_lib::audit_log::{AuditLog, AuditTool};
+use config_lib::change_events::ChangeSource;
+use config_lib::device_config::{DeviceConfig, ValueSource, WireEncoding, parse_override};
+use config_lib::merge::{MergeResult, load_sync_snapshot, merge_values, save_sync_snapshot};
+use config_lib::scheme_refresh::{DescribedValues, ParameterDescription, refresh_scheme};
+use log::{debug, info, warn};
 use protocol_lib::client::HostClient;
+use protocol_lib::dispatcher::ReplyFilter;
+use protocol_lib::opcode::Opcode;
+use protocol_lib::telemetry::{
+    TelemetryOptions, TelemetrySubscription, set_streaming_mode, subscribe,
+};
+
+use std::collections::BTreeMap;
 
 const BOARD_RESPONSE_TIMEOUT_MS: std::time::Duration = std::time::Duration::from_millis(5000);
 const SERIAL_PORT_CONFIG_PATH: &str = "rk_nku_configs/rk3399_scheme.toml";
-const NKU_DEVICE_CONFIG_PATH: &str = "rk_nku_configs/nku_scheme.toml";
-
-#[derive(Clone)]
-/// Режимы стриминга данных от устройства
-pub enum StreamingMode {
-    /// Без стриминга
-    __SilentMode = 0,
-    /// Отправка данных, в случае изменения их состояния (и наличия)
-    OnChangeMode = 1,
-    /// Отправка данных периодически, с заданным в настройках (periodicity) периодом
-    __PeriodicMode = 2,
-    /// Отправка данных по требованию
-    __OnDemandMode = 3,
-}
+pub const NKU_DEVICE_CONFIG_PATH: &str = "rk_nku_configs/nku_scheme.toml";
+/// Значения настроек индикатора на момент последней синхронизации (база для слияния при pull)
+const NKU_SYNC_SNAPSHOT_PATH: &str = "rk_nku_configs/nku_scheme.synced.toml";
+/// Префикс переменных окружения, переопределяющих значения схем (например, `NKU_BAUDRATE`)
+const ENV_OVERRIDE_PREFIX: &str = "NKU_";
+
+pub use protocol_lib::telemetry::StreamingMode;
 
 pub struct NkuClient {
     nku_client: HostClient,
@@ -26,99 +30,319 @@ pub struct NkuClient {
 }
 
 impl NkuClient {
-    pub fn new() -> Result<Self, String> {
-        // Чтение параметров последовательного порта
-        let serial_config = SerialPortConfig::new(SERIAL_PORT_CONFIG_PATH)?;
+    /// * `overrides` - аргументы вида `key=value`, переопределяющие значения схем без их сохранения
+    /// * `url` - транспорт для связи с платой (`None` - последовательный порт из схемы порта)
+    pub fn new(overrides: &[String], url: Option<&str>) -> Result<Self, String> {
+        let mut nku_config = DeviceConfig::create_parameter_list(NKU_DEVICE_CONFIG_PATH)?;
+        // Значения из окружения и командной строки вводятся вручную, допускаются вариации написания
+        nku_config.set_tolerant_matching(true);
+        nku_config.apply_env_overrides(ENV_OVERRIDE_PREFIX);
+
+        // Переопределения, не относящиеся к настройкам индикатора, передаются в схему порта
+        let mut serial_overrides = Vec::new();
+        for argument in overrides {
+            let (key, value) = parse_override(argument)?;
+            if nku_config.has_parameter(&key) {
+                nku_config.set_parameter_override(&key, value, ValueSource::CommandLine)?;
+            } else {
+                serial_overrides.push((key, value));
+            }
+        }
 
-        let nku_config = DeviceConfig::create_parameter_list(NKU_DEVICE_CONFIG_PATH)?;
         debug!("Parameters list: {:#?}", nku_config.get_parameters_names());
 
-        let port_name = serial_config.get_serial_name();
-        let baudrate = serial_config.get_serial_baudrate();
+        let nku_client = NkuClient::connect(&serial_overrides, url)?;
+
+        Ok(Self {
+            nku_client,
+            nku_config,
+        })
+    }
+
+    /// Подключение к интерфейсной плате через транспорт `url`
+    /// или с параметрами порта из схемы `SERIAL_PORT_CONFIG_PATH`, если `url` не задан
+    /// * `serial_overrides` - пары `key=value`, переопределяющие значения схемы порта
+    fn connect(
+        serial_overrides: &[(String, String)],
+        url: Option<&str>,
+    ) -> Result<HostClient, String> {
+        let url = match url {
+            Some(url) => {
+                if !serial_overrides.is_empty() {
+                    warn!("Serial port overrides are ignored: transport URL {url} is used");
+                }
+                url.to_string()
+            }
+            None => {
+                // Чтение параметров последовательного порта
+                let serial_config = SerialPortConfig::new(
+                    SERIAL_PORT_CONFIG_PATH,
+                    ENV_OVERRIDE_PREFIX,
+                    serial_overrides,
+                )?;
+
+                let port_name = serial_config.get_serial_name();
+                let baudrate = serial_config.get_serial_baudrate();
 
-        debug!("Serial config: {port_name}, {baudrate}");
+                debug!("Serial config: {port_name}, {baudrate}");
+                format!("serial://{port_name}?baud={baudrate}")
+            }
+        };
 
-        let nku_client = HostClient::connect(&port_name, baudrate, BOARD_RESPONSE_TIMEOUT_MS)?;
+        let nku_client = HostClient::connect_url(&url, BOARD_RESPONSE_TIMEOUT_MS)?;
 
         info!("Connection with IMv has been established!");
 
-        Ok(Self {
-            nku_client,
-            nku_config,
+        Ok(nku_client)
+    }
+
+    /// ### Построение (обновление) схемы `path_to_scheme` по описанию параметров от интерфейсной платы
+    /// Схема индикатора при этом не читается: она может отсутствовать или не соответствовать ПО платы.
+    /// Возвращает текущее и обновленное содержимое схемы (пустая строка - схема отсутствовала)
+    pub fn describe_board_scheme(
+        overrides: &[String],
+        url: Option<&str>,
+        path_to_scheme: &str,
+    ) -> Result<(String, String), String> {
+        let serial_overrides = overrides
+            .iter()
+            .map(|argument| parse_override(argument))
+            .collect::<Result<Vec<(String, String)>, String>>()?;
+        let mut nku_client = NkuClient::connect(&serial_overrides, url)?;
+
+        // Ожидается ответ в формате "list:parameter_1,parameter_2,..."
+        let response_from_mcu =
+            nku_client.send_request_matching("list", NkuClient::reply_filter("list"))?;
+        debug!("DESCRIBE> response from MCU: {response_from_mcu}");
+        let parameters_list = NkuClient::extract_parameter_value("list", response_from_mcu)?;
+
+        let mut descriptions = Vec::new();
+        for parameter in parameters_list
+            .split(',')
+            .map(|parameter| parameter.trim())
+            .filter(|parameter| !parameter.is_empty())
+        {
+            let request_string = format!("describe {parameter}");
+            debug!("DESCRIBE> sending request: {request_string}");
+            let response_from_mcu = nku_client
+                .send_request_matching(&request_string, NkuClient::reply_filter(parameter))?;
+            debug!("DESCRIBE> response from MCU: {response_from_mcu}");
+
+            let description = NkuClient::extract_parameter_value(parameter, response_from_mcu)?;
+            descriptions.push(NkuClient::parse_parameter_description(
+                parameter,
+                &description,
+            )?);
+        }
+
+        let current_scheme = match std::fs::read_to_string(path_to_scheme) {
+            Ok(scheme) => scheme,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e.to_string()),
+        };
+        let refreshed_scheme = refresh_scheme(&current_scheme, &descriptions)?;
+
+        Ok((current_scheme, refreshed_scheme))
+    }
+
+    /// Разбор описания параметра из ответа на `describe <parameter>`
+    /// Ожидается формат "encoding=u8;count=16;default=0" (индексные кодировки)
+    /// или "encoding=label;values=-1|1|2;default=1" (кодировка label), `default` - необязательно
+    fn parse_parameter_description(
+        parameter_name: &str,
+        description: &str,
+    ) -> Result<ParameterDescription, String> {
+        let mut encoding = WireEncoding::IndexU8;
+        let mut values = None;
+        let mut default = None;
+
+        for field in description
+            .split(';')
+            .filter(|field| !field.trim().is_empty())
+        {
+            let Some((name, value)) = field.split_once('=') else {
+                return Err(format!(
+                    "Failed to parse description of {parameter_name}: {description}"
+                ));
+            };
+            match name.trim() {
+                "encoding" => encoding = value.trim().parse()?,
+                "count" => {
+                    let count = value
+                        .trim()
+                        .parse::<usize>()
+                        .map_err(|e| format!("Invalid count of {parameter_name}: {e}"))?;
+                    values = Some(DescribedValues::Count(count));
+                }
+                "values" => {
+                    values = Some(DescribedValues::Labels(
+                        value.split('|').map(|label| label.to_string()).collect(),
+                    ))
+                }
+                "default" => default = Some(value.trim().to_string()),
+                _ => debug!("DESCRIBE> unknown field of {parameter_name}: {field}"),
+            }
+        }
+
+        let Some(values) = values else {
+            return Err(format!(
+                "Description of {parameter_name} has no values: {description}"
+            ));
+        };
+
+        Ok(ParameterDescription {
+            key: parameter_name.to_string(),
+            encoding,
+            values,
+            default,
         })
     }
 
+    /// ### Получение текущей конфигурации индикатора
+    pub fn get_device_config(&self) -> &DeviceConfig {
+        &self.nku_config
+    }
+
+    /// ### Подключение журнала изменений, в него записываются изменения, полученные от устройства
+    pub fn set_audit_log(&mut self, audit_log: AuditLog) {
+        self.nku_config
+            .set_audit_log(audit_log, AuditTool::SyncPull);
+    }
+
     /// ### Запрос начала стриминга данных со станции управления
     pub fn start_elevator_data_streaming(&mut self, mode: StreamingMode) -> Result<String, String> {
-        let response_from_mcu = self
-            .nku_client
-            .send_request(format!("set mode {}", mode.clone() as u8).as_str())?;
-        if !response_from_mcu.contains(format!("mode: {}", mode as u8).as_str()) {
-            return Err("START STREAMING> invalid response".into());
-        }
-        Ok(response_from_mcu)
+        set_streaming_mode(&mut self.nku_client, mode).map_err(|e| format!("START STREAMING> {e}"))
+    }
+
+    /// ### Подписка на телеметрию от станции управления
+    /// Схема индикатора не читается; после ошибок связи подключение выполняется заново
+    /// * `overrides` - аргументы вида `key=value`, переопределяющие значения схемы порта
+    /// * `url` - транспорт для связи с платой (`None` - последовательный порт из схемы порта)
+    pub fn subscribe_telemetry(
+        overrides: &[String],
+        url: Option<&str>,
+        options: TelemetryOptions,
+    ) -> Result<TelemetrySubscription, String> {
+        let serial_overrides = overrides
+            .iter()
+            .map(|argument| parse_override(argument))
+            .collect::<Result<Vec<(String, String)>, String>>()?;
+        let url = url.map(|url| url.to_string());
+
+        Ok(subscribe(
+            move || NkuClient::connect(&serial_overrides, url.as_deref()),
+            options,
+        ))
     }
 
-    /// ### Запрос сохраненных в устройстве настроек
-    pub fn pull_parameters_from_device(&mut self) -> Result<(), String> {
+    /// ### Запрос сохраненных в устройстве настроек и слияние их с локальными
+    /// База слияния - значения последней синхронизации; если синхронизация не выполнялась,
+    /// значения устройства имеют приоритет. Настройки не сохраняются (см. `apply_merge`)
+    pub fn pull_parameters_from_device(&mut self) -> Result<MergeResult, String> {
         let parameters_list = self.nku_config.get_parameters_names()?;
+        let mut device_config = self.nku_config.clone();
 
         for parameter in parameters_list {
             let request_string = format!("get {parameter}");
             debug!("PULL> sending request: {request_string}");
-            let response_from_mcu = self.nku_client.send_request(&request_string)?;
+            let response_from_mcu = self
+                .nku_client
+                .send_request_matching(&request_string, NkuClient::reply_filter(&parameter))?;
             debug!("PULL> response from MCU: {response_from_mcu}");
 
             let parameter_value =
                 NkuClient::extract_parameter_value(&parameter, response_from_mcu)?;
-            self.nku_config
-                .set_parameter_value_using_index(&parameter, parameter_value)?;
+            device_config.set_parameter_value_from_wire(&parameter, &parameter_value)?;
+        }
+
+        let local_values = self.nku_config.get_parameters_scheme_values()?;
+        let device_values = device_config.get_parameters_scheme_values()?;
+        let base_values = match load_sync_snapshot(NKU_SYNC_SNAPSHOT_PATH)? {
+            Some(base_values) => base_values,
+            None => {
+                warn!("PULL> no sync snapshot, device values take precedence");
+                local_values.clone()
+            }
+        };
+
+        Ok(merge_values(&base_values, &local_values, &device_values))
+    }
+
+    /// ### Сохранение результата слияния настроек (все конфликты должны быть разрешены)
+    /// Снимок синхронизации - значения устройства: сохраненные локальные изменения остаются
+    /// изменениями относительно снимка до их отправки на устройство (push)
+    pub fn apply_merge(&mut self, merge: &MergeResult) -> Result<(), String> {
+        if let Some(conflict) = merge.conflicts.first() {
+            return Err(format!("Unresolved merge conflict: {}", conflict.key));
+        }
+
+        for key in merge.from_device.iter() {
+            if let Some(value) = merge.values.get(key) {
+                self.nku_config.set_parameter_value_from(
+                    key,
+                    value.clone(),
+                    ChangeSource::Device,
+                )?;
+            }
         }
         self.nku_config.save_parameters_values()?;
-        Ok(())
+        save_sync_snapshot(NKU_SYNC_SNAPSHOT_PATH, &merge.device)
+    }
+
+    /// Признаки отклика на запрос параметра `parameter_name`: консольный фрейм "parameter_name:..."
+    /// Фреймы телеметрии и запоздавшие отклики на другие запросы не принимаются за отклик
+    fn reply_filter(parameter_name: &str) -> ReplyFilter {
+        ReplyFilter::prefix(Opcode::Console, format!("{parameter_name}:").as_bytes())
     }
 
     /// Извлечение значения параметра из отклика от MCU
     fn extract_parameter_value(
         parameter_name: &str,
         response_from_mcu: String,
-    ) -> Result<u8, String> {
-        // Ожидается ответ в формате "parameter_name:value"
-        let tokens = response_from_mcu.split(':').collect::<Vec<&str>>().to_vec();
-
-        if tokens.len() != 2 {
+    ) -> Result<String, String> {
+        // Ожидается ответ в формате "parameter_name:value", value - в кодировке параметра (индекс или значение)
+        let Some((name, value)) = response_from_mcu.split_once(':') else {
             return Err(format!(
                 "Failed to parse response from MCU: {response_from_mcu}"
             ));
-        }
+        };
 
-        if tokens[0] != parameter_name {
+        if name != parameter_name {
             return Err(format!(
                 "Failed to parse response from MCU: {response_from_mcu}"
             ));
         }
 
-        tokens[1].trim().parse::<u8>().map_err(|e| e.to_string())
+        Ok(value.trim().to_string())
     }
 
     /// Отправка новых настроек на устройство для последующего сохранения
+    /// Отправка не выполняется, если нарушено одно из ограничений схемы
     pub fn push_parameters_to_device(&mut self) -> Result<(), String> {
+        self.nku_config.validate()?;
+
         let parameters_list = self.nku_config.get_parameters_names()?;
+        // Значения, подтвержденные платой (с учетом переопределений), - база следующего слияния
+        let mut pushed_values = BTreeMap::new();
 
         for parameter in parameters_list {
-            let parameter_value = self
-                .nku_config
-                .get_parameter_index_using_value(&parameter)?;
+            let parameter_value = self.nku_config.get_parameter_wire_value(&parameter)?;
             let request_string = format!("set {parameter} {parameter_value}");
             debug!("PUSH> sending request: {request_string}");
-            let response_from_mcu = self.nku_client.send_request(&request_string)?;
+            let response_from_mcu = self
+                .nku_client
+                .send_request_matching(&request_string, NkuClient::reply_filter(&parameter))?;
 
             debug!("PUSH> response from MCU: {response_from_mcu}");
             if !response_from_mcu.contains(format!("{parameter}: {parameter_value}").as_str()) {
                 return Err("PUSH> invalid response".into());
             }
+            pushed_values.insert(
+                parameter.clone(),
+                self.nku_config.get_parameter_value(&parameter)?,
+            );
         }
 
-        Ok(())
+        save_sync_snapshot(NKU_SYNC_SNAPSHOT_PATH, &pushed_values)
     }
 }
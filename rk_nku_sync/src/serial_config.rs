@@ -1,4 +1,4 @@
-use config_lib::device_config::DeviceConfig;
+use config_lib::device_config::{DeviceConfig, ValueSource};
 use log::debug;
 
 pub struct SerialPortConfig {
@@ -8,17 +8,38 @@ pub struct SerialPortConfig {
 
 impl SerialPortConfig {
     /// Чтение параметров последовательного порта из файла-схемы TOML
-    pub fn new(path_to_scheme: &str) -> Result<Self, String> {
-        let nku_serial_parameters = DeviceConfig::create_parameter_list(path_to_scheme)?;
+    /// * `env_prefix` - префикс переменных окружения, переопределяющих значения схемы
+    /// * `overrides` - пары `key=value` из командной строки, переопределяющие значения схемы
+    pub fn new(
+        path_to_scheme: &str,
+        env_prefix: &str,
+        overrides: &[(String, String)],
+    ) -> Result<Self, String> {
+        let mut nku_serial_parameters = DeviceConfig::create_parameter_list(path_to_scheme)?;
 
-        let serial_name = nku_serial_parameters.get_parameter_value("device")?;
-        let serial_baudrate = nku_serial_parameters
-            .get_parameter_value("baudrate")?
+        nku_serial_parameters.set_tolerant_matching(true);
+        nku_serial_parameters.apply_env_overrides(env_prefix);
+        for (key, value) in overrides {
+            nku_serial_parameters.set_parameter_override(
+                key,
+                value.clone(),
+                ValueSource::CommandLine,
+            )?;
+        }
+
+        let (serial_name, serial_name_source) =
+            nku_serial_parameters.get_parameter_value_with_source("device")?;
+        let (serial_baudrate, serial_baudrate_source) =
+            nku_serial_parameters.get_parameter_value_with_source("baudrate")?;
+        let serial_baudrate = serial_baudrate
             .parse::<u32>()
             .map_err(|e: std::num::ParseIntError| e.to_string())?;
 
-        debug!("serial port_name: {}", serial_name);
-        debug!("serial baudrate: {}", serial_baudrate);
+        debug!("serial port_name: {} ({})", serial_name, serial_name_source);
+        debug!(
+            "serial baudrate: {} ({})",
+            serial_baudrate, serial_baudrate_source
+        );
 
         Ok(SerialPortConfig {
             serial_name,